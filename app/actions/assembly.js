@@ -2,8 +2,17 @@
 import type {
     GraphState
 } from 'react-graph-editor';
+import fs from 'fs';
+import {
+    remote,
+} from 'electron';
 import {
     build,
+    buildDocument,
+    decompile,
+    disassemble,
+    extractGraph,
+    inferTypes,
 } from '../utils/rasen.render';
 
 type Dispatcher = (action: Object) => void;
@@ -15,22 +24,124 @@ export function setMode(evt: any) {
     };
 }
 
-export function updateAssembly(graph: GraphState) {
+export function updateAssembly(graph: GraphState, stage: ?string) {
     return (dispatch: Dispatcher) => {
-        build(graph)
-            .then(result =>
+        build(graph, stage)
+            .then(result => {
                 dispatch({
                     type: 'UPDATE_ASSEMBLY',
                     asm: result.asm,
                     glsl: result.glsl,
-                })
-            )
-            .catch(error =>
+                });
+                dispatch({
+                    type: 'UPDATE_TYPES',
+                    types: result.types,
+                });
+            })
+            .catch(({ message, diagnostics }) => {
                 dispatch({
                     type: 'UPDATE_ASSEMBLY',
-                    asm: { error },
+                    asm: { error: message, diagnostics },
                     glsl: '',
+                });
+
+                // The build only checks the types of graphs that are whole,
+                // the others still get theirs inferred for the editor
+                dispatch(updateTypes(graph, stage));
+            });
+    };
+}
+
+export function updateTypes(graph: GraphState, stage: ?string) {
+    return (dispatch: Dispatcher) => {
+        inferTypes(graph, stage)
+            .then(({ types }) =>
+                dispatch({
+                    type: 'UPDATE_TYPES',
+                    types,
+                })
+            )
+            .catch(() =>
+                dispatch({
+                    type: 'UPDATE_TYPES',
+                    types: {},
                 })
             );
     };
 }
+
+export function openBytecode() {
+    return (dispatch: Dispatcher) => {
+        remote.dialog.showOpenDialog({
+            properties: ['openFile'],
+            filters: [{
+                name: 'SPIR-V Binary',
+                extensions: ['spv'],
+            }],
+        }, paths => {
+            if (!paths) {
+                return;
+            }
+
+            // Modules exported by the editor carry their graph, which gives
+            // a listing with the same names as the live build
+            extractGraph(paths[0])
+                .then(buildDocument)
+                .catch(() =>
+                    disassemble(paths[0])
+                        .then(asm => ({ asm, glsl: '' }))
+                )
+                .then(result =>
+                    dispatch({
+                        type: 'UPDATE_ASSEMBLY',
+                        asm: result.asm,
+                        glsl: result.glsl,
+                    })
+                )
+                .catch(({ message, diagnostics }) =>
+                    dispatch({
+                        type: 'UPDATE_ASSEMBLY',
+                        asm: { error: message, diagnostics },
+                        glsl: '',
+                    })
+                );
+        });
+    };
+}
+
+export function decompileBytecode() {
+    return () => {
+        remote.dialog.showOpenDialog({
+            properties: ['openFile'],
+            filters: [{
+                name: 'SPIR-V Binary',
+                extensions: ['spv'],
+            }],
+        }, paths => {
+            if (!paths) {
+                return;
+            }
+
+            decompile(paths[0])
+                .then(graph => {
+                    remote.dialog.showSaveDialog({
+                        filters: [{
+                            name: 'Graph Document',
+                            extensions: ['json'],
+                        }],
+                    }, path => {
+                        if (!path) {
+                            return;
+                        }
+
+                        fs.writeFile(path, JSON.stringify(graph, null, '    '), err => {
+                            if (err) {
+                                console.error(err);
+                            }
+                        });
+                    });
+                })
+                .catch(error => remote.dialog.showErrorBox('Decompile', error.message));
+        });
+    };
+}
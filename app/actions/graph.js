@@ -8,11 +8,19 @@ import type {
 } from 'react-graph-editor';
 
 import {
+    GRAPH_VERSION,
     exportGraph,
+    upgradeGraph,
 } from '../utils/rasen.render';
 
+export {
+    openBytecode,
+    decompileBytecode,
+} from './assembly';
+
 export const UPDATE_GRAPH = 'UPDATE_GRAPH';
 export const ADD_NODE = 'ADD_NODE';
+export const SET_STAGE = 'SET_STAGE';
 
 export const FILE_NEW = 'FILE_NEW';
 export const FILE_OPEN = 'FILE_OPEN';
@@ -44,6 +52,13 @@ export function addNode(node: Node) {
     };
 }
 
+export function setStage(evt: any) {
+    return {
+        type: SET_STAGE,
+        stage: evt.target.value,
+    };
+}
+
 export function newFile() {
     return {
         type: FILE_NEW
@@ -62,29 +77,52 @@ export function open() {
                 extensions: ['*']
             }]
         }, ([path]) => {
-            fs.readFile(path, (err, data) => {
+            fs.readFile(path, 'utf8', (err, data) => {
                 if (err) {
                     return console.error(err);
                 }
 
-                dispatch({
-                    type: FILE_OPEN,
-                    data: JSON.parse(data),
-                    path,
-                });
+                upgradeGraph(data)
+                    .then(({ graph, from, to, changes }) => {
+                        if (changes.length > 0) {
+                            remote.dialog.showMessageBox({
+                                type: 'info',
+                                title: 'Open',
+                                message: `This graph was upgraded from version ${from} to ${to}`,
+                                detail: changes.join('\n'),
+                            });
+                        }
+
+                        dispatch({
+                            type: FILE_OPEN,
+                            data: graph,
+                            stage: graph.stage,
+                            path,
+                        });
+                    })
+                    .catch(error => remote.dialog.showErrorBox('Open', error.message));
             });
         });
     };
 }
 
+// Saved files carry the document version so they can be upgraded when opened
+function saveGraph(graph: GraphState, stage: string) {
+    return JSON.stringify({
+        ...graph.save(),
+        version: GRAPH_VERSION,
+        stage,
+    }, null, '    ');
+}
+
 export function save() {
     return (dispatch: Dispatcher, getState: StateGetter) => {
         const {
-            path, graph
+            path, graph, stage
         } = getState();
 
         if (path) {
-            fs.writeFile(path, JSON.stringify(graph.save(), null, '    '), err => {
+            fs.writeFile(path, saveGraph(graph, stage), err => {
                 if (err) {
                     return console.error(err);
                 }
@@ -109,10 +147,10 @@ export function saveAs() {
             }]
         }, path => {
             const {
-                graph
+                graph, stage
             } = getState();
 
-            fs.writeFile(path, JSON.stringify(graph.save(), null, '    '), err => {
+            fs.writeFile(path, saveGraph(graph, stage), err => {
                 if (err) {
                     return console.error(err);
                 }
@@ -139,10 +177,10 @@ export function saveBC() {
         }, path => {
             if (path) {
                 const {
-                    graph
+                    graph, stage
                 } = getState();
 
-                exportGraph(graph, path);
+                exportGraph(graph, stage, path);
             }
         });
     };
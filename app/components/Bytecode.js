@@ -1,6 +1,5 @@
 // @flow
 import React from 'react';
-import leftPad from 'left-pad';
 
 import SyntaxHighlighter from 'react-syntax-highlighter';
 
@@ -22,7 +21,8 @@ const Assembly = ({ assembly }: { assembly: Object }) => {
                     {inst.result_id ? (
                         <span>
                             <span className={styles.id}>
-                                {leftPad(`%${inst.result_id}`, padding - 3)}
+                                {' '.repeat(Math.max(0, padding - 4 - String(inst.result_id).length))}
+                                %{inst.result_id}
                             </span>
                             {' = '}
                         </span>
@@ -99,7 +99,9 @@ const Assembly = ({ assembly }: { assembly: Object }) => {
 
 type Props = {
     setMode: (evt: any) => void,
+    setStage: (evt: any) => void,
     mode: string,
+    stage: string,
     assembly: ?Object,
     glsl: string,
 };
@@ -148,6 +150,10 @@ export default (props: Props) => {
                 <option>Assembly</option>
                 <option>GLSL</option>
             </select>
+            <select value={props.stage} onChange={props.setStage}>
+                <option value="fragment">Fragment</option>
+                <option value="vertex">Vertex</option>
+            </select>
             {content}
         </div>
     );
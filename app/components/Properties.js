@@ -13,6 +13,7 @@ import styles from './Properties.css';
 
 type Props = {
     graph: GraphState,
+    types: Object,
     updateGraph: (nextState: GraphState) => void
 };
 
@@ -168,7 +169,29 @@ const PINS = new List([
     'x', 'y', 'z', 'w'
 ]).map(name => new Pin({ name }));
 
-export default ({ graph, updateGraph }: Props) => (
+// Types inferred by the compiler for the pins of a node, unknown types are
+// left out
+function pinTypes(types: Object, node): Array<{ name: string, type: string }> {
+    const nodeTypes = types[node.id];
+    if (!nodeTypes) {
+        return [];
+    }
+
+    const res = nodeTypes.inputs
+        .map((type, i) => ({
+            name: node.inputs.get(i) ? node.inputs.get(i).name : `input ${i}`,
+            type,
+        }));
+
+    res.push({
+        name: 'result',
+        type: nodeTypes.output,
+    });
+
+    return res.filter(({ type }) => type !== null);
+}
+
+export default ({ graph, types, updateGraph }: Props) => (
     <SplitPane className={styles.properties} split="vertical" defaultSize={250}>
         <div className={styles.column}>
             {
@@ -183,6 +206,15 @@ export default ({ graph, updateGraph }: Props) => (
                     )
                     .toArray()
             }
+            {
+                graph.selectedNodes
+                    .take(1)
+                    .flatMap(node => pinTypes(types, node))
+                    .map(({ name }) => (
+                        <label key={`pin-${name}`}>{name}</label>
+                    ))
+                    .toArray()
+            }
         </div>
         <div className={styles.column}>
             {
@@ -229,6 +261,15 @@ export default ({ graph, updateGraph }: Props) => (
                     )
                     .toArray()
             }
+            {
+                graph.selectedNodes
+                    .take(1)
+                    .flatMap(node => pinTypes(types, node))
+                    .map(({ name, type }) => (
+                        <span key={`pin-${name}`}>{type}</span>
+                    ))
+                    .toArray()
+            }
         </div>
     </SplitPane>
 );
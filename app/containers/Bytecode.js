@@ -4,17 +4,24 @@ import { connect } from 'react-redux';
 
 import Bytecode from '../components/Bytecode';
 import * as AsmActions from '../actions/assembly';
+import {
+    setStage,
+} from '../actions/graph';
 
 function mapStateToProps(state) {
     return {
         mode: state.assembly.mode,
+        stage: state.stage,
         assembly: state.assembly.assembly,
         glsl: state.assembly.glsl,
     };
 }
 
 function mapDispatchToProps(dispatch) {
-    return bindActionCreators(AsmActions, dispatch);
+    return bindActionCreators({
+        ...AsmActions,
+        setStage,
+    }, dispatch);
 }
 
 export default connect(mapStateToProps, mapDispatchToProps)(Bytecode);
@@ -7,7 +7,8 @@ import * as GraphActions from '../actions/graph';
 
 function mapStateToProps(state) {
     return {
-        graph: state.graph
+        graph: state.graph,
+        types: state.assembly.types,
     };
 }
 
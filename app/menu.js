@@ -70,6 +70,20 @@ export default [{
                 focusedWindow.webContents.send('action', 'saveBC');
             }
         }
+    }, {
+        label: 'Disassemble ...',
+        click(evt, focusedWindow) {
+            if (focusedWindow) {
+                focusedWindow.webContents.send('action', 'openBytecode');
+            }
+        }
+    }, {
+        label: 'Decompile ...',
+        click(evt, focusedWindow) {
+            if (focusedWindow) {
+                focusedWindow.webContents.send('action', 'decompileBytecode');
+            }
+        }
     }, {
         type: 'separator',
     }, {
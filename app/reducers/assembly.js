@@ -7,6 +7,7 @@ const Assembly = Record({
     mode: 'assembly',
     assembly: null,
     glsl: '',
+    types: {},
 });
 
 export default function assembly(state: Assembly = new Assembly(), action: Object) {
@@ -23,6 +24,9 @@ export default function assembly(state: Assembly = new Assembly(), action: Objec
                 action.glsl
             );
 
+        case 'UPDATE_TYPES':
+            return state.set('types', action.types);
+
         default:
             return state;
     }
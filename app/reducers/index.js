@@ -3,11 +3,12 @@ import { combineReducers } from 'redux';
 
 import graph from './graph';
 import path from './path';
+import stage from './stage';
 import assembly from './assembly';
 import viewport from './viewport';
 
 const rootReducer = combineReducers({
-    path, graph, assembly, viewport,
+    path, stage, graph, assembly, viewport,
 });
 
 export default rootReducer;
@@ -0,0 +1,25 @@
+// @flow
+import {
+    SET_STAGE,
+    FILE_NEW,
+    FILE_OPEN,
+} from '../actions/graph';
+import {
+    DEFAULT_STAGE,
+} from '../utils/rasen.render';
+
+export default function stage(state: string = DEFAULT_STAGE, action: Object) {
+    switch (action.type) {
+        case SET_STAGE:
+            return action.stage;
+
+        case FILE_NEW:
+            return DEFAULT_STAGE;
+
+        case FILE_OPEN:
+            return action.stage || DEFAULT_STAGE;
+
+        default:
+            return state;
+    }
+}
@@ -10,8 +10,9 @@ export default ({ dispatch, getState }) => next => action => {
     if (
         currentState === undefined ||
         currentState.graph.editorState.nodes !== nextState.graph.editorState.nodes ||
-        currentState.graph.editorState.edges !== nextState.graph.editorState.edges
+        currentState.graph.editorState.edges !== nextState.graph.editorState.edges ||
+        currentState.stage !== nextState.stage
     ) {
-        dispatch(updateAssembly(nextState.graph));
+        dispatch(updateAssembly(nextState.graph, nextState.stage));
     }
 };
@@ -6,28 +6,131 @@ import {
 import {
     ipcMain,
 } from 'electron';
-import leftPad from 'left-pad';
 
 import ffi from 'ffi';
 import ref from 'ref';
 
-const rasen = ffi.Library(path.join(__dirname, '../../native/target/release/rasen'), {
-    to_bytecode: ['pointer', ['string']],
-    to_assembly: ['string', ['string']],
+const rasen = ffi.Library(path.join(__dirname, '../../native/target/release/rasen_bridge'), {
+    rasen_to_bytecode: ['pointer', ['string']],
+    rasen_to_bytecode_ext: ['pointer', ['string', 'uint32']],
+    rasen_to_spvasm: ['pointer', ['string']],
+    rasen_to_spvasm_ext: ['pointer', ['string', 'uint32']],
+    rasen_compile: ['pointer', ['string']],
+    rasen_infer_types: ['pointer', ['string']],
+    rasen_upgrade_graph: ['pointer', ['string']],
+    rasen_disassemble: ['pointer', ['pointer', 'size_t']],
+    rasen_to_graph: ['pointer', ['pointer', 'size_t']],
+    rasen_extract_graph: ['pointer', ['pointer', 'size_t']],
+    rasen_free_buffer: ['void', ['pointer']],
+    rasen_free_string: ['void', ['pointer']],
 });
 
-function toAssembly(graph) {
+const FRIENDLY_NAMES = 4;
+
+function toSpvasm(graph, flags = 0) {
+    return Promise.resolve()
+        .then(() => {
+            const ptr = rasen.rasen_to_spvasm_ext(graph, flags);
+            try {
+                return JSON.parse(ref.readCString(ptr, 0));
+            } finally {
+                rasen.rasen_free_string(ptr);
+            }
+        });
+}
+
+function compile(graph) {
+    return Promise.resolve()
+        .then(() => {
+            const ptr = rasen.rasen_compile(graph);
+            try {
+                return JSON.parse(ref.readCString(ptr, 0));
+            } finally {
+                rasen.rasen_free_string(ptr);
+            }
+        });
+}
+
+function inferTypes(graph) {
+    return Promise.resolve()
+        .then(() => {
+            const ptr = rasen.rasen_infer_types(graph);
+            try {
+                return JSON.parse(ref.readCString(ptr, 0));
+            } finally {
+                rasen.rasen_free_string(ptr);
+            }
+        });
+}
+
+function upgradeGraph(graph) {
+    return Promise.resolve()
+        .then(() => {
+            const ptr = rasen.rasen_upgrade_graph(graph);
+            try {
+                return JSON.parse(ref.readCString(ptr, 0));
+            } finally {
+                rasen.rasen_free_string(ptr);
+            }
+        });
+}
+
+function disassemble(buffer) {
+    return Promise.resolve()
+        .then(() => {
+            const ptr = rasen.rasen_disassemble(buffer, buffer.length);
+            try {
+                return JSON.parse(ref.readCString(ptr, 0));
+            } finally {
+                rasen.rasen_free_string(ptr);
+            }
+        });
+}
+
+function toGraph(buffer) {
     return Promise.resolve()
-        .then(() => rasen.to_assembly(graph));
+        .then(() => {
+            const ptr = rasen.rasen_to_graph(buffer, buffer.length);
+            try {
+                return JSON.parse(ref.readCString(ptr, 0));
+            } finally {
+                rasen.rasen_free_string(ptr);
+            }
+        });
+}
+
+function extractGraph(buffer) {
+    return Promise.resolve()
+        .then(() => {
+            const ptr = rasen.rasen_extract_graph(buffer, buffer.length);
+            try {
+                return JSON.parse(ref.readCString(ptr, 0));
+            } finally {
+                rasen.rasen_free_string(ptr);
+            }
+        });
 }
 
-function toBytecode(graph) {
+const EMBED_GRAPH = 1;
+
+function toBytecode(graph, flags = 0) {
     return Promise.resolve()
         .then(() => {
-            const ptr = rasen.to_bytecode(graph);
-            const sizePtr = ref.reinterpret(ptr, 8);
-            const size = sizePtr.readUIntLE(0, 8);
-            return ref.reinterpret(ptr, size, 8);
+            const ptr = rasen.rasen_to_bytecode_ext(graph, flags);
+            try {
+                const array = ref.reinterpret(ptr, 24);
+                const status = array.readUInt32LE(0);
+                const size = array.readUIntLE(8, 6);
+                const data = Buffer.from(ref.readPointer(array, 16, size));
+
+                if (status !== 0) {
+                    throw JSON.parse(data.toString()).error;
+                }
+
+                return data;
+            } finally {
+                rasen.rasen_free_buffer(ptr);
+            }
         });
 }
 
@@ -56,16 +159,25 @@ function toGLSL(buffer) {
     });
 }
 
+// Errors of the bridge come with the diagnostics pointing at the nodes, edges
+// and pins at fault, anything else is only a message
+function sendError(sender, channel, id, error) {
+    sender.send(channel, id, {
+        error: error.error || String(error),
+        diagnostics: error.diagnostics || [],
+    });
+}
+
 ipcMain.on('build', async ({ sender }, id, graph) => {
     try {
-        const asm = JSON.parse(
-            await toAssembly(graph)
-        );
-        if (asm.error) {
-            throw asm.error;
+        const result = await compile(graph);
+        if (result.error) {
+            throw result;
         }
 
-        const bin = await toBytecode(graph);
+        const asm = result.assembly;
+        const bin = Buffer.alloc(result.bytecode.length * 4);
+        result.bytecode.forEach((word, i) => bin.writeUInt32LE(word, i * 4));
 
         let glsl;
         try {
@@ -77,59 +189,126 @@ ipcMain.on('build', async ({ sender }, id, graph) => {
         sender.send('build', id, {
             payload: {
                 asm, bin, glsl,
+                types: result.types,
+                warnings: result.warnings,
             },
         });
     } catch (error) {
-        sender.send('build', id, { error });
+        sendError(sender, 'build', id, error);
     }
 });
 
+ipcMain.on('types', async ({ sender }, id, graph) => {
+    try {
+        const result = await inferTypes(graph);
+        if (result.error) {
+            throw result;
+        }
+
+        sender.send('types', id, {
+            payload: {
+                types: result.types,
+                diagnostics: result.diagnostics,
+            },
+        });
+    } catch (error) {
+        sendError(sender, 'types', id, error);
+    }
+});
+
+ipcMain.on('upgrade', async ({ sender }, id, graph) => {
+    try {
+        const result = await upgradeGraph(graph);
+        if (result.error) {
+            throw result;
+        }
+
+        sender.send('upgrade', id, {
+            payload: {
+                graph: result.graph,
+                from: result.from,
+                to: result.to,
+                changes: result.changes,
+            },
+        });
+    } catch (error) {
+        sendError(sender, 'upgrade', id, error);
+    }
+});
+
+ipcMain.on('disassemble', ({ sender }, id, file) => {
+    fs.readFile(file, async (err, data) => {
+        if (err) {
+            return sender.send('disassemble', id, { error: err.message });
+        }
+
+        try {
+            const asm = await disassemble(data);
+            if (asm.error) {
+                throw asm;
+            }
+
+            sender.send('disassemble', id, { payload: asm });
+        } catch (error) {
+            sendError(sender, 'disassemble', id, error);
+        }
+    });
+});
+
+ipcMain.on('decompile', ({ sender }, id, file) => {
+    fs.readFile(file, async (err, data) => {
+        if (err) {
+            return sender.send('decompile', id, { error: err.message });
+        }
+
+        try {
+            const graph = await toGraph(data);
+            if (graph.error) {
+                throw graph;
+            }
+
+            sender.send('decompile', id, { payload: graph });
+        } catch (error) {
+            sendError(sender, 'decompile', id, error);
+        }
+    });
+});
+
+ipcMain.on('extract', ({ sender }, id, file) => {
+    fs.readFile(file, async (err, data) => {
+        if (err) {
+            return sender.send('extract', id, { error: err.message });
+        }
+
+        try {
+            const graph = await extractGraph(data);
+            if (graph.error) {
+                throw graph;
+            }
+
+            sender.send('extract', id, { payload: graph });
+        } catch (error) {
+            sendError(sender, 'extract', id, error);
+        }
+    });
+});
+
 ipcMain.on('export', async (evt, graph, file) => {
     let data;
     switch (path.extname(file)) {
         case '.spv':
-            data = await toBytecode(graph);
+            data = await toBytecode(graph, EMBED_GRAPH);
             break;
 
         case '.spvasm': {
-            const tree = JSON.parse(
-                await toAssembly(graph)
-            );
+            const result = await toSpvasm(graph, FRIENDLY_NAMES);
 
-            if (tree.error) {
-                console.error('error', tree.error);
+            if (result.error) {
+                console.error('error', result.error);
                 return;
             }
 
-            let padding = 5;
-            while (tree.bound > 10) {
-                padding += 1;
-                tree.bound /= 10;
-            }
-
-            data = tree.instructions
-                .map((inst, key) => `${
-                    inst.result_id ?
-                        `${leftPad(`%${inst.result_id}`, padding - 3)} = ` :
-                        new Array(inst.class === ';' ? 0 : padding).join(' ')
-                }${inst.class} ${
-                    inst.operands
-                        .map((op, i) => {
-                            switch (op.operand) {
-                                case 'Id':
-                                case 'Type':
-                                    return `%${op.value}`;
-
-                                case 'String':
-                                    return `"${op.value}"`;
-
-                                default:
-                                    return op.value;
-                            }
-                        })
-                        .join(' ')
-                }`)
-                .join('\n');
+            data = result.text;
         }
         break;
 
@@ -2,7 +2,15 @@ import {
     ipcRenderer,
 } from 'electron';
 
-const serializeGraph = ({ editorState }) => JSON.stringify({
+// Version of the graph documents understood by the native compiler
+export const GRAPH_VERSION = 1;
+
+// Stage of the graphs that don't specify one
+export const DEFAULT_STAGE = 'fragment';
+
+const serializeGraph = ({ editorState }, stage) => JSON.stringify({
+    version: GRAPH_VERSION,
+    stage: stage || DEFAULT_STAGE,
     nodes: editorState.nodes
         .map(({ title, data }) => ({
             ...data
@@ -19,28 +27,66 @@ const serializeGraph = ({ editorState }) => JSON.stringify({
 let rpcId = 0;
 const handlers = {};
 
-ipcRenderer.on('build', (evt, id, result) => {
+function onResult(evt, id, result) {
     if (handlers[id]) {
         handlers[id](result);
         delete handlers[id];
     }
-});
+}
+
+ipcRenderer.on('build', onResult);
+ipcRenderer.on('disassemble', onResult);
+ipcRenderer.on('decompile', onResult);
+ipcRenderer.on('extract', onResult);
+ipcRenderer.on('types', onResult);
+ipcRenderer.on('upgrade', onResult);
 
-export function build(graph) {
+function call(channel, ...args) {
     return new Promise((resolve, reject) => {
         const id = rpcId++;
         handlers[id] = result => {
             if (result.error) {
-                reject(result.error);
+                reject({
+                    message: result.error,
+                    diagnostics: result.diagnostics || [],
+                });
             } else {
                 resolve(result.payload);
             }
         };
 
-        ipcRenderer.send('build', id, serializeGraph(graph));
+        ipcRenderer.send(channel, id, ...args);
     });
 }
 
-export function exportGraph(graph, file) {
-    ipcRenderer.send('export', serializeGraph(graph), file);
+export function build(graph, stage) {
+    return call('build', serializeGraph(graph, stage));
+}
+
+export function buildDocument(document) {
+    return call('build', JSON.stringify(document));
+}
+
+export function inferTypes(graph, stage) {
+    return call('types', serializeGraph(graph, stage));
+}
+
+export function upgradeGraph(document) {
+    return call('upgrade', typeof document === 'string' ? document : JSON.stringify(document));
+}
+
+export function disassemble(file) {
+    return call('disassemble', file);
+}
+
+export function decompile(file) {
+    return call('decompile', file);
+}
+
+export function extractGraph(file) {
+    return call('extract', file);
+}
+
+export function exportGraph(graph, stage, file) {
+    ipcRenderer.send('export', serializeGraph(graph, stage), file);
 }
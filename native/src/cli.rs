@@ -0,0 +1,632 @@
+extern crate glob;
+extern crate serde_json;
+extern crate rasen_bridge;
+
+use std::cmp;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use serde_json::{to_string, Value, Map};
+
+use rasen_bridge::*;
+
+const USAGE: &'static str = "usage: rasen-cli [options] <graph.json | directory | glob>...
+
+options:
+    -o, --output <path>     output file, or output directory when compiling several graphs
+    -f, --format <format>   spv, json or text (defaults to the output extension, or spv)
+    -j, --jobs <count>      number of graphs compiled in parallel
+    --report <path>         write a JSON summary of the build, - for stdout
+    --watch                 recompile graphs when they change
+    --embed-graph           store the source graph in the SPIR-V module
+    --debug-names           name result ids after their graph node
+    --friendly-names        print readable ids in assembly output";
+
+// Exit codes
+const COMPILE_ERROR: i32 = 1;
+const USAGE_ERROR: i32 = 2;
+const IO_ERROR: i32 = 3;
+
+const DEFAULT_JOBS: usize = 4;
+const WATCH_INTERVAL: u64 = 500;
+
+#[derive(Clone, Copy)]
+enum Format {
+    Binary,
+    Json,
+    Text,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "spv" => Some(Format::Binary),
+            "json" => Some(Format::Json),
+            "text" | "spvasm" => Some(Format::Text),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match *self {
+            Format::Binary => "spv",
+            // Keeps listings apart from the graphs they are compiled from
+            Format::Json => "listing.json",
+            Format::Text => "spvasm",
+        }
+    }
+}
+
+struct Options {
+    inputs: Vec<String>,
+    output: Option<PathBuf>,
+    format: Option<Format>,
+    flags: u32,
+    jobs: usize,
+    report: Option<PathBuf>,
+    watch: bool,
+}
+
+fn parse_args<I>(mut args: I) -> Result<Options, String> where I: Iterator<Item=String> {
+    let mut options = Options {
+        inputs: Vec::new(),
+        output: None,
+        format: None,
+        flags: 0,
+        jobs: DEFAULT_JOBS,
+        report: None,
+        watch: false,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                let path = args.next().ok_or(format!("{} expects a path", arg))?;
+                options.output = Some(PathBuf::from(path));
+            },
+            "-f" | "--format" => {
+                let name = args.next().ok_or(format!("{} expects a format", arg))?;
+                options.format = Some(Format::from_name(&name).ok_or(format!("Unknown format {:?}", name))?);
+            },
+            "-j" | "--jobs" => {
+                let count = args.next().ok_or(format!("{} expects a number", arg))?;
+                options.jobs = match count.parse() {
+                    Ok(0) | Err(_) => return Err(format!("Invalid job count {:?}", count)),
+                    Ok(count) => count,
+                };
+            },
+            "--report" => {
+                let path = args.next().ok_or(format!("{} expects a path", arg))?;
+                options.report = Some(PathBuf::from(path));
+            },
+            "--watch" => options.watch = true,
+            "--embed-graph" => options.flags |= EMBED_GRAPH,
+            "--debug-names" => options.flags |= DEBUG_NAMES,
+            "--friendly-names" => options.flags |= FRIENDLY_NAMES,
+            "-h" | "--help" => return Err(String::from(USAGE)),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+            _ => options.inputs.push(arg),
+        }
+    }
+
+    if options.inputs.is_empty() {
+        return Err(String::from(USAGE));
+    }
+
+    Ok(options)
+}
+
+fn is_pattern(input: &str) -> bool {
+    input.contains(|c: char| c == '*' || c == '?' || c == '[')
+}
+
+// Listings written by earlier JSON builds share the extension of the graphs
+fn is_graph(path: &Path) -> bool {
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+    name.ends_with(".json") && !name.ends_with(Format::Json.extension())
+}
+
+// A graph file along with its path relative to the directory or pattern it
+// was found through, used to lay out a batch in the output directory
+struct Source {
+    path: PathBuf,
+    relative: PathBuf,
+}
+
+impl Source {
+    fn new(path: PathBuf, root: &Path) -> Source {
+        let relative = match path.strip_prefix(root) {
+            Ok(relative) if relative.file_name().is_some() => relative.to_path_buf(),
+            _ => PathBuf::from(path.file_name().unwrap_or(path.as_os_str())),
+        };
+
+        Source {
+            path: path,
+            relative: relative,
+        }
+    }
+}
+
+fn collect_dir(dir: &Path, res: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        entries.push(entry?.path());
+    }
+
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect_dir(&path, res)?;
+        } else if is_graph(&path) {
+            res.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+// Leading directories of a pattern that contain no wildcard
+fn pattern_root(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .parent()
+        .map(|parent| parent.components()
+            .take_while(|part| !is_pattern(&part.as_os_str().to_string_lossy()))
+            .collect())
+        .unwrap_or_else(PathBuf::new)
+}
+
+// Resolve directories and glob patterns to the graph files they contain
+fn expand_inputs(inputs: &[String]) -> Result<Vec<Source>, String> {
+    let mut res = Vec::new();
+
+    for input in inputs {
+        if is_pattern(input) {
+            let paths = glob::glob(input)
+                .map_err(|err| format!("Invalid pattern {:?}: {}", input, err))?;
+
+            let root = pattern_root(input);
+            for path in paths {
+                let path = path.map_err(|err| format!("{}", err))?;
+                if path.is_file() {
+                    res.push(Source::new(path, &root));
+                }
+            }
+        } else {
+            let path = PathBuf::from(input);
+            if path.is_dir() {
+                let mut paths = Vec::new();
+                collect_dir(&path, &mut paths)
+                    .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+                res.extend(paths.into_iter().map(|file| Source::new(file, &path)));
+            } else {
+                let root = path.parent().map(Path::to_path_buf).unwrap_or_else(PathBuf::new);
+                res.push(Source::new(path, &root));
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    res.retain(|source| seen.insert(source.path.clone()));
+
+    Ok(res)
+}
+
+fn is_batch(options: &Options) -> bool {
+    options.inputs.len() > 1 ||
+    options.inputs.iter().any(|input| is_pattern(input) || Path::new(input).is_dir())
+}
+
+fn output_format(options: &Options, batch: bool) -> Format {
+    match (options.format, &options.output) {
+        (Some(format), _) => format,
+        (None, &Some(ref output)) if !batch => output.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Format::from_name)
+            .unwrap_or(Format::Binary),
+        _ => Format::Binary,
+    }
+}
+
+// A single input is written to the output path as is, a batch is written to
+// the output directory with the layout of its input directories
+fn output_path(options: &Options, source: &Source, format: Format, batch: bool) -> PathBuf {
+    match options.output {
+        Some(ref output) if !batch => output.clone(),
+        Some(ref output) => output.join(&source.relative).with_extension(format.extension()),
+        None => source.path.with_extension(format.extension()),
+    }
+}
+
+struct Job {
+    input: PathBuf,
+    output: PathBuf,
+    format: Format,
+    flags: u32,
+}
+
+struct Outcome {
+    input: PathBuf,
+    output: PathBuf,
+    size: Option<usize>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+fn read_file(path: &Path) -> io::Result<String> {
+    let mut data = String::new();
+    File::open(path)?.read_to_string(&mut data)?;
+    Ok(data)
+}
+
+// Returns the compiled output along with the warnings emitted on the way
+fn compile(input: String, format: Format, flags: u32) -> Result<(Vec<u8>, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let program = rasen_bridge::compile(&input, flags)?;
+
+    let output = match format {
+        Format::Binary => program.bytes(),
+        Format::Json | Format::Text => {
+            let listing = program.listing(flags)?;
+
+            let text = match format {
+                Format::Json => to_string(&listing.to_value())
+                    .map_err(|err| vec![Diagnostic::error(format!("{}", err))])?,
+                _ => listing.to_text(),
+            };
+
+            text.into_bytes()
+        },
+    };
+
+    Ok((output, program.parsed.warnings))
+}
+
+fn run_job(job: &Job) -> Outcome {
+    let mut outcome = Outcome {
+        input: job.input.clone(),
+        output: job.output.clone(),
+        size: None,
+        diagnostics: Vec::new(),
+    };
+
+    if job.output == job.input {
+        outcome.diagnostics.push(Diagnostic::error("output would overwrite the input"));
+        return outcome;
+    }
+
+    let input = match read_file(&job.input) {
+        Ok(input) => input,
+        Err(err) => {
+            outcome.diagnostics.push(Diagnostic::error(format!("{}", err)));
+            return outcome;
+        },
+    };
+
+    let code = match compile(input, job.format, job.flags) {
+        Ok((code, warnings)) => {
+            outcome.diagnostics = warnings;
+            code
+        },
+        Err(errors) => {
+            outcome.diagnostics = errors;
+            return outcome;
+        },
+    };
+
+    let written = match job.output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    };
+    let written = written
+        .and_then(|_| File::create(&job.output))
+        .and_then(|mut file| file.write_all(&code));
+
+    match written {
+        Ok(_) => outcome.size = Some(code.len()),
+        Err(err) => outcome.diagnostics.push(
+            Diagnostic::error(format!("{}: {}", job.output.display(), err))
+        ),
+    }
+
+    outcome
+}
+
+// Compile the jobs on a pool of threads, keeping the outcomes in job order
+fn run_jobs(jobs: Vec<Job>, threads: usize) -> Vec<Outcome> {
+    let count = jobs.len();
+    let queue = Arc::new(Mutex::new(
+        jobs.into_iter().enumerate().collect::<VecDeque<_>>()
+    ));
+
+    let (sender, receiver) = mpsc::channel();
+    let workers: Vec<_> = (0..cmp::min(threads, count))
+        .map(|_| {
+            let queue = queue.clone();
+            let sender = sender.clone();
+
+            thread::spawn(move || loop {
+                let next = queue.lock().unwrap().pop_front();
+                match next {
+                    Some((index, job)) => sender.send((index, run_job(&job))).unwrap(),
+                    None => break,
+                }
+            })
+        })
+        .collect();
+
+    drop(sender);
+
+    let mut outcomes: Vec<_> = receiver.iter().collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    outcomes.sort_by_key(|&(index, _)| index);
+    outcomes.into_iter()
+        .map(|(_, outcome)| outcome)
+        .collect()
+}
+
+fn failures(outcomes: &[Outcome]) -> usize {
+    outcomes.iter()
+        .filter(|outcome| outcome.size.is_none())
+        .count()
+}
+
+fn print_outcomes(outcomes: &[Outcome]) {
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+
+    for outcome in outcomes {
+        for diag in &outcome.diagnostics {
+            writeln!(stderr, "{}: {}", outcome.input.display(), diag).unwrap();
+        }
+    }
+
+    let failed = failures(outcomes);
+    if outcomes.len() > 1 || failed > 0 {
+        writeln!(stderr, "compiled {} graphs, {} failed", outcomes.len() - failed, failed).unwrap();
+    }
+}
+
+fn report_value(outcomes: &[Outcome]) -> Value {
+    let files = outcomes.iter()
+        .map(|outcome| {
+            let mut res = Map::new();
+            res.insert(String::from("input"), Value::String(format!("{}", outcome.input.display())));
+            res.insert(String::from("output"), Value::String(format!("{}", outcome.output.display())));
+            res.insert(String::from("success"), Value::Bool(outcome.size.is_some()));
+            res.insert(String::from("size"), match outcome.size {
+                Some(size) => Value::U64(size as u64),
+                None => Value::Null,
+            });
+            res.insert(String::from("diagnostics"), Value::Array(
+                outcome.diagnostics.iter()
+                    .map(Diagnostic::to_value)
+                    .collect()
+            ));
+
+            Value::Object(res)
+        })
+        .collect();
+
+    let failed = failures(outcomes);
+
+    let mut res = Map::new();
+    res.insert(String::from("files"), Value::Array(files));
+    res.insert(String::from("succeeded"), Value::U64((outcomes.len() - failed) as u64));
+    res.insert(String::from("failed"), Value::U64(failed as u64));
+
+    Value::Object(res)
+}
+
+fn write_report(path: &Path, outcomes: &[Outcome]) -> io::Result<()> {
+    let report = to_string(&report_value(outcomes))
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{}", err)))?;
+
+    if path == Path::new("-") {
+        println!("{}", report);
+        Ok(())
+    } else {
+        File::create(path)?.write_all(report.as_bytes())
+    }
+}
+
+// Pair each input with its output, dropping the inputs that are the output
+// of another input so a build never compiles its own listings
+fn plan_jobs(options: &Options, sources: Vec<Source>) -> Vec<Job> {
+    let batch = is_batch(options);
+    let format = output_format(options, batch);
+
+    let jobs: Vec<_> = sources.iter()
+        .map(|source| Job {
+            input: source.path.clone(),
+            output: output_path(options, source, format, batch),
+            format: format,
+            flags: options.flags,
+        })
+        .collect();
+
+    let produced: HashSet<_> = jobs.iter()
+        .map(|job| job.output.clone())
+        .collect();
+
+    jobs.into_iter()
+        .filter(|job| !produced.contains(&job.input))
+        .collect()
+}
+
+// Several inputs with the same relative path would overwrite each other
+fn check_outputs(jobs: &[Job]) -> Result<(), String> {
+    let mut outputs: HashMap<&Path, &Path> = HashMap::new();
+    for job in jobs {
+        if let Some(other) = outputs.insert(&job.output, &job.input) {
+            return Err(format!(
+                "{} and {} would both be written to {}",
+                other.display(), job.input.display(), job.output.display()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// Compile the given jobs once, returns whether all of them succeeded or the
+// exit code of a fatal error
+fn build_all(options: &Options, jobs: Vec<Job>) -> Result<bool, i32> {
+    check_outputs(&jobs).map_err(|msg| {
+        writeln!(io::stderr(), "{}", msg).unwrap();
+        USAGE_ERROR
+    })?;
+
+    let outcomes = run_jobs(jobs, options.jobs);
+    print_outcomes(&outcomes);
+
+    if let Some(ref report) = options.report {
+        write_report(report, &outcomes).map_err(|err| {
+            writeln!(io::stderr(), "{}: {}", report.display(), err).unwrap();
+            IO_ERROR
+        })?;
+    }
+
+    Ok(failures(&outcomes) == 0)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+// Poll the inputs and recompile the graphs that changed since the last build,
+// directories and patterns are expanded again to pick up new files
+fn watch(options: &Options) -> ! {
+    let mut stamps = HashMap::new();
+
+    loop {
+        let sources = expand_inputs(&options.inputs).unwrap_or_else(|err| {
+            writeln!(io::stderr(), "{}", err).unwrap();
+            Vec::new()
+        });
+
+        let changed: Vec<_> = plan_jobs(options, sources).into_iter()
+            .filter(|job| {
+                let stamp = modified(&job.input);
+                stamps.insert(job.input.clone(), stamp) != Some(stamp)
+            })
+            .collect();
+
+        if !changed.is_empty() {
+            let _ = build_all(options, changed);
+        }
+
+        thread::sleep(Duration::from_millis(WATCH_INTERVAL));
+    }
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(msg) => {
+            writeln!(io::stderr(), "{}", msg).unwrap();
+            process::exit(USAGE_ERROR);
+        },
+    };
+
+    if options.watch {
+        watch(&options);
+    }
+
+    let sources = match expand_inputs(&options.inputs) {
+        Ok(sources) => sources,
+        Err(msg) => {
+            writeln!(io::stderr(), "{}", msg).unwrap();
+            process::exit(IO_ERROR);
+        },
+    };
+
+    match build_all(&options, plan_jobs(&options, sources)) {
+        Ok(true) => {},
+        Ok(false) => process::exit(COMPILE_ERROR),
+        Err(code) => process::exit(code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    fn options(args: &[&str]) -> Options {
+        parse_args(args.iter().map(|arg| String::from(*arg))).unwrap()
+    }
+
+    fn source(path: &str, relative: &str) -> Source {
+        Source {
+            path: PathBuf::from(path),
+            relative: PathBuf::from(relative),
+        }
+    }
+
+    fn job(input: &str, output: &str) -> Job {
+        Job {
+            input: PathBuf::from(input),
+            output: PathBuf::from(output),
+            format: Format::Binary,
+            flags: 0,
+        }
+    }
+
+    #[test]
+    fn listings_are_not_graphs() {
+        assert!(is_graph(Path::new("shaders/normalize.json")));
+        assert!(!is_graph(Path::new("shaders/normalize.listing.json")));
+        assert!(!is_graph(Path::new("shaders/normalize.spv")));
+    }
+
+    #[test]
+    fn outputs_follow_the_inputs() {
+        let source = source("shaders/lighting/phong.json", "lighting/phong.json");
+
+        let single = options(&["shaders/lighting/phong.json"]);
+        assert_eq!(output_path(&single, &source, Format::Binary, false), Path::new("shaders/lighting/phong.spv"));
+        assert_eq!(output_path(&single, &source, Format::Json, false), Path::new("shaders/lighting/phong.listing.json"));
+
+        let named = options(&["-o", "out/shader.bin", "shaders/lighting/phong.json"]);
+        assert_eq!(output_path(&named, &source, Format::Binary, false), Path::new("out/shader.bin"));
+
+        let batch = options(&["-o", "out", "shaders/lighting/phong.json", "shaders/flat.json"]);
+        assert_eq!(output_path(&batch, &source, Format::Text, true), Path::new("out/lighting/phong.spvasm"));
+    }
+
+    #[test]
+    fn listings_of_the_batch_are_not_compiled() {
+        let options = options(&["-f", "json", "shaders/a.json", "shaders/a.listing.json", "shaders/b.json"]);
+        let jobs = plan_jobs(&options, vec![
+            source("shaders/a.json", "a.json"),
+            source("shaders/a.listing.json", "a.listing.json"),
+            source("shaders/b.json", "b.json"),
+        ]);
+
+        let inputs: Vec<_> = jobs.iter().map(|job| job.input.clone()).collect();
+        assert_eq!(inputs, vec![PathBuf::from("shaders/a.json"), PathBuf::from("shaders/b.json")]);
+
+        let outputs: Vec<_> = jobs.iter().map(|job| job.output.clone()).collect();
+        assert_eq!(outputs, vec![PathBuf::from("shaders/a.listing.json"), PathBuf::from("shaders/b.listing.json")]);
+    }
+
+    #[test]
+    fn shared_outputs_are_rejected() {
+        assert!(check_outputs(&[job("a/x.json", "out/x.spv"), job("b/y.json", "out/y.spv")]).is_ok());
+        assert_eq!(
+            check_outputs(&[job("a/x.json", "out/x.spv"), job("b/x.json", "out/x.spv")]),
+            Err(String::from("a/x.json and b/x.json would both be written to out/x.spv"))
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use error_chain::ChainedError;
+use rasen::*;
+
+use debug::*;
+use diagnostic::*;
+use embed::*;
+use interface::*;
+use names::*;
+use parser::*;
+use printer::*;
+
+pub fn print_err<E>(e: E) -> String where E: ChainedError {
+    let mut err = String::new();
+    writeln!(&mut err, "{}", e).unwrap();
+
+    for e in e.iter().skip(1) {
+        writeln!(&mut err, "caused by: {}", e).unwrap();
+    }
+
+    if let Some(backtrace) = e.backtrace() {
+        writeln!(&mut err, "backtrace: {:?}", backtrace).unwrap();
+    }
+
+    err
+}
+
+pub fn build_err<E>(e: E) -> Vec<Diagnostic> where E: ChainedError {
+    vec![Diagnostic::error(print_err(e))]
+}
+
+pub fn print_failed(msg: String) -> Vec<Diagnostic> {
+    vec![Diagnostic::error(msg)]
+}
+
+/// Store the source graph in the module so it can be recovered with `extract_graph`
+pub const EMBED_GRAPH: u32 = 1;
+/// Name every result id after the graph node it was built from
+pub const DEBUG_NAMES: u32 = 2;
+/// Print ids with readable names derived from their type or node in the assembly
+pub const FRIENDLY_NAMES: u32 = 4;
+
+/// A graph built into a SPIR-V module
+pub struct Program {
+    /// The checked graph the module was built from
+    pub parsed: ParsedGraph,
+    /// SPIR-V words of the module, including the embedded graph and debug
+    /// names when they were requested
+    pub words: Vec<u32>,
+    /// Key of the graph node each result id was built from
+    pub source_map: BTreeMap<u32, String>,
+}
+
+impl Program {
+    /// Encode the module as a little-endian SPIR-V binary
+    pub fn bytes(&self) -> Vec<u8> {
+        words_to_bytes(&self.words)
+    }
+
+    /// Decode the module for printing, `FRIENDLY_NAMES` in `flags` gives
+    /// result ids readable names
+    pub fn listing(&self, flags: u32) -> Result<Listing, Vec<Diagnostic>> {
+        let mut listing = decode_words(&self.words).map_err(print_failed)?;
+        if flags & FRIENDLY_NAMES != 0 {
+            listing.names = friendly_names(&listing);
+        }
+
+        Ok(listing)
+    }
+}
+
+// Output location standing in for a bug in rasen in the tests: a checked
+// graph writing to it panics where the module would be built
+#[cfg(test)]
+pub const PANIC_LOCATION: u32 = 0xdead;
+
+#[cfg(test)]
+fn check_panic(parsed: &ParsedGraph) {
+    use typeck::Kind;
+
+    for kind in parsed.nodes.values() {
+        if let Kind::Output(PANIC_LOCATION, _) = *kind {
+            panic!("building output {}", PANIC_LOCATION);
+        }
+    }
+}
+
+pub fn build(input: String, flags: u32) -> Result<Program, Vec<Diagnostic>> {
+    let parsed = parse_input(input.clone())?;
+
+    #[cfg(test)]
+    check_panic(&parsed);
+
+    let code = build_program(&parsed.graph, parsed.stage)
+        .map_err(build_err)?;
+
+    let mut words = words_from_bytes(&code).map_err(print_failed)?;
+    if let ShaderType::Fragment = parsed.stage {
+        words = decorate_flat_inputs(&words).map_err(print_failed)?;
+    }
+
+    let source_map = source_map(
+        &decode_words(&words).map_err(print_failed)?,
+        &parsed
+    );
+
+    if flags & EMBED_GRAPH != 0 {
+        words = embed_graph(&words, &input).map_err(print_failed)?;
+    }
+    if flags & DEBUG_NAMES != 0 {
+        words = emit_names(&words, &source_map).map_err(print_failed)?;
+    }
+
+    Ok(Program {
+        parsed: parsed,
+        words: words,
+        source_map: source_map,
+    })
+}
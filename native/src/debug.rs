@@ -0,0 +1,258 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{Value, Map};
+
+use embed::*;
+use parser::*;
+use printer::*;
+use typeck::Kind;
+
+const OP_NAME: u32 = 5;
+
+// The debug instructions that names must follow
+pub const DEBUG_SOURCE: &'static [u32] = &[
+    2, // OpSourceContinued
+    3, // OpSource
+    4, // OpSourceExtension
+    5, // OpName
+    6, // OpMemberName
+    7, // OpString
+];
+
+fn value_ids<'a, I>(operands: I) -> Vec<u32> where I: Iterator<Item=&'a Operand> {
+    operands
+        .filter_map(|op| match *op {
+            Operand::Id(id) => Some(id),
+            _ => None,
+        })
+        .collect()
+}
+
+// Walks the module from its stored outputs alongside the graph edges, pairing
+// the operands of each instruction with the inputs of the matching node
+struct Tracer<'a> {
+    defs: HashMap<u32, &'a Instruction>,
+    inputs: HashMap<&'a str, BTreeMap<u32, &'a str>>,
+    ids: BTreeMap<u32, String>,
+}
+
+impl<'a> Tracer<'a> {
+    fn trace(&mut self, id: u32, key: &'a str) {
+        if self.ids.contains_key(&id) {
+            return;
+        }
+
+        self.ids.insert(id, String::from(key));
+
+        let inst = match self.defs.get(&id) {
+            Some(inst) => *inst,
+            None => return,
+        };
+
+        let args = match inst.class.as_str() {
+            "OpLoad" => {
+                if let Some(&Operand::Id(var)) = inst.operands.get(1) {
+                    self.ids.entry(var).or_insert_with(|| String::from(key));
+                }
+                return;
+            },
+            "OpExtInst" => value_ids(inst.operands.iter().skip(3)),
+            class if class.starts_with("OpConstant") => return,
+            _ => value_ids(inst.operands.iter().skip(1)),
+        };
+
+        let inputs: Vec<_> = match self.inputs.get(key) {
+            Some(inputs) => inputs.values().cloned().collect(),
+            None => return,
+        };
+
+        // Instructions the compiler added on its own can't be matched
+        if inputs.len() != args.len() {
+            return;
+        }
+
+        for (arg, from) in args.into_iter().zip(inputs) {
+            self.trace(arg, from);
+        }
+    }
+}
+
+/// Find the graph node key each result id of a module was built from
+pub fn source_map(listing: &Listing, parsed: &ParsedGraph) -> BTreeMap<u32, String> {
+    let mut defs = HashMap::new();
+    let mut locations = HashMap::new();
+    let mut stores = HashMap::new();
+
+    for inst in &listing.instructions {
+        if let Some(id) = inst.result_id {
+            defs.insert(id, inst);
+        }
+
+        match (inst.class.as_str(), inst.operands.get(0), inst.operands.get(1), inst.operands.get(2)) {
+            ("OpDecorate", Some(&Operand::Id(target)), Some(&Operand::Enum(ref decoration)), Some(&Operand::LitInt(location))) => {
+                if decoration == "Location" {
+                    locations.insert(target, location);
+                }
+            },
+            ("OpStore", Some(&Operand::Id(var)), Some(&Operand::Id(value)), _) => {
+                stores.insert(var, value);
+            },
+            _ => {},
+        }
+    }
+
+    let mut inputs = HashMap::new();
+    for edge in &parsed.edges {
+        inputs.entry(edge.to.as_str())
+            .or_insert_with(BTreeMap::new)
+            .insert(edge.input, edge.from.as_str());
+    }
+
+    let mut tracer = Tracer {
+        defs: defs,
+        inputs: inputs,
+        ids: BTreeMap::new(),
+    };
+
+    for (key, kind) in &parsed.nodes {
+        let location = match *kind {
+            Kind::Output(location, _) => location,
+            _ => continue,
+        };
+
+        let var = stores.keys()
+            .find(|var| locations.get(*var) == Some(&location))
+            .cloned();
+
+        if let Some(var) = var {
+            tracer.ids.insert(var, key.clone());
+
+            let source = tracer.inputs.get(key.as_str())
+                .and_then(|inputs| inputs.get(&0))
+                .cloned();
+
+            if let Some(source) = source {
+                tracer.trace(stores[&var], source);
+            }
+        }
+    }
+
+    tracer.ids
+}
+
+pub fn source_map_value(map: &BTreeMap<u32, String>) -> Value {
+    let mut res = Map::new();
+    for (id, key) in map {
+        res.insert(format!("{}", id), Value::String(key.clone()));
+    }
+
+    Value::Object(res)
+}
+
+/// Name every mapped result id after its node key with OpName instructions
+pub fn emit_names(words: &[u32], map: &BTreeMap<u32, String>) -> Result<Vec<u32>, String> {
+    let mut preamble = PREAMBLE.to_vec();
+    preamble.extend_from_slice(DEBUG_SOURCE);
+    let offset = section_offset(words, &preamble)?;
+
+    let mut names = Vec::new();
+    for (id, key) in map {
+        let mut operands = vec![*id];
+        operands.extend(encode_string(key));
+        push_instruction(&mut names, OP_NAME, &operands);
+    }
+
+    let mut res = Vec::with_capacity(words.len() + names.len());
+    res.extend_from_slice(&words[..offset]);
+    res.extend(names);
+    res.extend_from_slice(&words[offset..]);
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use embed::*;
+    use printer::*;
+
+    use super::{emit_names, source_map};
+
+    // The module of tests/fixtures/normalize.json: %7 is the input, %12 loads
+    // it, %13 normalizes it and %9 is the output
+    fn normalize() -> Vec<u32> {
+        let mut words = vec![MAGIC_NUMBER, 0x00010000, 0, 14, 0];
+        push_instruction(&mut words, 17, &[1]);
+
+        let mut import = vec![1];
+        import.extend(encode_string("GLSL.std.450"));
+        push_instruction(&mut words, 11, &import);
+        push_instruction(&mut words, 14, &[0, 1]);
+
+        let mut entry = vec![4, 10];
+        entry.extend(encode_string("main"));
+        entry.extend_from_slice(&[7, 9]);
+        push_instruction(&mut words, 15, &entry);
+        push_instruction(&mut words, 16, &[10, 7]);
+
+        push_instruction(&mut words, 71, &[7, 30, 0]);
+        push_instruction(&mut words, 71, &[9, 30, 0]);
+
+        push_instruction(&mut words, 19, &[2]);
+        push_instruction(&mut words, 33, &[3, 2]);
+        push_instruction(&mut words, 22, &[4, 32]);
+        push_instruction(&mut words, 23, &[5, 4, 3]);
+        push_instruction(&mut words, 32, &[6, 1, 5]);
+        push_instruction(&mut words, 59, &[6, 7, 1]);
+        push_instruction(&mut words, 32, &[8, 3, 5]);
+        push_instruction(&mut words, 59, &[8, 9, 3]);
+
+        push_instruction(&mut words, 54, &[2, 10, 0, 3]);
+        push_instruction(&mut words, 248, &[11]);
+        push_instruction(&mut words, 61, &[5, 12, 7]);
+        push_instruction(&mut words, 12, &[5, 13, 1, 69, 12]);
+        push_instruction(&mut words, 62, &[9, 13]);
+        push_instruction(&mut words, 253, &[]);
+        push_instruction(&mut words, 56, &[]);
+
+        words
+    }
+
+    fn map() -> BTreeMap<u32, String> {
+        let parsed = ::parse_graph(include_str!("../tests/fixtures/normalize.json")).unwrap();
+        source_map(&decode_words(&normalize()).unwrap(), &parsed)
+    }
+
+    #[test]
+    fn ids_map_to_their_nodes() {
+        let map = map();
+
+        assert_eq!(map.get(&7).map(String::as_str), Some("0"));
+        assert_eq!(map.get(&12).map(String::as_str), Some("0"));
+        assert_eq!(map.get(&13).map(String::as_str), Some("1"));
+        assert_eq!(map.get(&9).map(String::as_str), Some("2"));
+    }
+
+    #[test]
+    fn names_go_in_the_debug_section() {
+        let words = emit_names(&normalize(), &map()).unwrap();
+        let listing = decode_words(&words).unwrap();
+
+        let classes: Vec<_> = listing.instructions.iter()
+            .map(|inst| inst.class.as_str())
+            .collect();
+        let first = classes.iter().position(|class| *class == "OpName").unwrap();
+        let last = classes.iter().rposition(|class| *class == "OpName").unwrap();
+
+        assert_eq!(classes[first - 1], "OpExecutionMode");
+        assert_eq!(classes[last + 1], "OpDecorate");
+
+        let named = listing.instructions[first..last + 1].iter()
+            .any(|inst| match (inst.operands.get(0), inst.operands.get(1)) {
+                (Some(&Operand::Id(13)), Some(&Operand::LitString(ref name))) => name == "1",
+                _ => false,
+            });
+        assert!(named);
+    }
+}
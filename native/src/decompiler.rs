@@ -0,0 +1,604 @@
+use std::collections::HashMap;
+
+use serde_json::{Value, Map};
+
+use migrate::CURRENT_VERSION;
+use printer::*;
+
+enum Pointer {
+    Input(u32),
+    Output(u32),
+}
+
+struct Variable {
+    storage: &'static str,
+    type_name: String,
+}
+
+// Rebuilds an editor graph from a straight-line shader, keyed the same way
+// the editor serializes its nodes
+struct Decompiler {
+    stage: Option<&'static str>,
+    type_names: HashMap<u32, String>,
+    pointers: HashMap<u32, Pointer>,
+    locations: HashMap<u32, u32>,
+    variables: HashMap<u32, Variable>,
+    inputs: HashMap<u32, u64>,
+    outputs: HashMap<u32, u64>,
+    values: HashMap<u32, u64>,
+    constants: HashMap<u32, (String, Value)>,
+    nodes: Map<String, Value>,
+    edges: Vec<Value>,
+    functions: u32,
+    labels: u32,
+}
+
+fn operand_id(operand: Option<&Operand>) -> Result<u32, String> {
+    match operand {
+        Some(&Operand::Id(id)) | Some(&Operand::Type(id)) => Ok(id),
+        _ => Err(String::from("Expected an id operand")),
+    }
+}
+
+fn operand_int(operand: Option<&Operand>) -> Result<u32, String> {
+    match operand {
+        Some(&Operand::LitInt(val)) => Ok(val),
+        _ => Err(String::from("Expected an integer operand")),
+    }
+}
+
+fn operand_enum(operand: Option<&Operand>) -> Result<&str, String> {
+    match operand {
+        Some(&Operand::Enum(ref val)) => Ok(val),
+        _ => Err(String::from("Expected an enum operand")),
+    }
+}
+
+fn literal_value(operand: Option<&Operand>) -> Result<Value, String> {
+    Ok(match operand {
+        Some(&Operand::LitInt(val)) => Value::U64(val as u64),
+        Some(&Operand::LitSInt(val)) => Value::I64(val as i64),
+        Some(&Operand::LitLong(val)) => Value::U64(val),
+        Some(&Operand::LitSLong(val)) => Value::I64(val),
+        Some(&Operand::LitFloat(val)) => Value::F64(val as f64),
+        Some(&Operand::LitDouble(val)) => Value::F64(val),
+        _ => return Err(String::from("Expected a numeric literal")),
+    })
+}
+
+fn scalar_prefix(type_name: &str) -> Result<&'static str, String> {
+    Ok(match type_name {
+        "bool" => "b",
+        "int" => "i",
+        "uint" => "u",
+        "float" => "",
+        "double" => "d",
+        _ => return Err(format!("{} is not a scalar type", type_name)),
+    })
+}
+
+pub fn node_title(inst: &Instruction) -> Option<&'static str> {
+    Some(match inst.class.as_str() {
+        "OpFAdd" | "OpIAdd" => "Add",
+        "OpFSub" | "OpISub" => "Substract",
+        "OpFMul" | "OpIMul" |
+        "OpVectorTimesScalar" | "OpMatrixTimesScalar" |
+        "OpVectorTimesMatrix" | "OpMatrixTimesVector" |
+        "OpMatrixTimesMatrix" => "Multiply",
+        "OpFDiv" | "OpSDiv" | "OpUDiv" => "Divide",
+        // The remainders take the sign of the dividend, which Modulus doesn't
+        "OpFMod" | "OpSMod" | "OpUMod" => "Modulus",
+        "OpDot" => "Dot",
+        _ => return None,
+    })
+}
+
+pub fn glsl_title(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "Normalize" => "Normalize",
+        "FClamp" | "SClamp" | "UClamp" => "Clamp",
+        "FMix" => "Mix",
+        "Cross" => "Cross",
+        "Floor" => "Floor",
+        "Ceil" => "Ceil",
+        "Round" => "Round",
+        "Sin" => "Sin",
+        "Cos" => "Cos",
+        "Tan" => "Tan",
+        "Pow" => "Pow",
+        "FMin" | "SMin" | "UMin" => "Min",
+        "FMax" | "SMax" | "UMax" => "Max",
+        "Length" => "Length",
+        "Distance" => "Distance",
+        "Reflect" => "Reflect",
+        "Refract" => "Refract",
+        _ => return None,
+    })
+}
+
+impl Decompiler {
+    fn new() -> Decompiler {
+        Decompiler {
+            stage: None,
+            type_names: HashMap::new(),
+            pointers: HashMap::new(),
+            locations: HashMap::new(),
+            variables: HashMap::new(),
+            inputs: HashMap::new(),
+            outputs: HashMap::new(),
+            values: HashMap::new(),
+            constants: HashMap::new(),
+            nodes: Map::new(),
+            edges: Vec::new(),
+            functions: 0,
+            labels: 0,
+        }
+    }
+
+    fn add_node(&mut self, title: &str, mut props: Map<String, Value>) -> u64 {
+        let key = self.nodes.len() as u64;
+        props.insert(String::from("title"), Value::String(String::from(title)));
+        self.nodes.insert(format!("{}", key), Value::Object(props));
+        key
+    }
+
+    fn add_edge(&mut self, from: u64, to: u64, input: usize) {
+        let mut edge = Map::new();
+        edge.insert(String::from("from"), Value::U64(from));
+        edge.insert(String::from("to"), Value::U64(to));
+        edge.insert(String::from("input"), Value::U64(input as u64));
+        self.edges.push(Value::Object(edge));
+    }
+
+    fn type_name(&self, id: u32) -> Result<String, String> {
+        self.type_names.get(&id)
+            .cloned()
+            .ok_or(format!("Type %{} is not supported", id))
+    }
+
+    // Resolve the node producing a value, materializing constants on first use
+    fn value(&mut self, id: u32) -> Result<u64, String> {
+        if let Some(key) = self.values.get(&id) {
+            return Ok(*key);
+        }
+
+        let (type_name, value) = self.constants.get(&id)
+            .cloned()
+            .ok_or(format!("Value %{} is not defined", id))?;
+
+        let mut props = Map::new();
+        props.insert(String::from("type"), Value::String(type_name));
+        props.insert(String::from("value"), value);
+
+        let key = self.add_node("Constant", props);
+        self.values.insert(id, key);
+        Ok(key)
+    }
+
+    fn connect(&mut self, to: u64, args: &[Operand]) -> Result<(), String> {
+        for (index, arg) in args.iter().enumerate() {
+            let from = self.value(operand_id(Some(arg))?)?;
+            self.add_edge(from, to, index);
+        }
+
+        Ok(())
+    }
+
+    fn result(inst: &Instruction) -> Result<u32, String> {
+        inst.result_id.ok_or(format!("{} has no result id", inst.class))
+    }
+
+    fn declare_type(&mut self, inst: &Instruction) -> Result<(), String> {
+        let id = Decompiler::result(inst)?;
+        let ops = &inst.operands;
+
+        let name = match inst.class.as_str() {
+            "OpTypeBool" => String::from("bool"),
+            "OpTypeInt" => match (operand_int(ops.get(0))?, operand_int(ops.get(1))?) {
+                (32, 0) => String::from("uint"),
+                (32, _) => String::from("int"),
+                (width, _) => return Err(format!("{}-bit integers are not supported", width)),
+            },
+            "OpTypeFloat" => match operand_int(ops.get(0))? {
+                32 => String::from("float"),
+                64 => String::from("double"),
+                width => return Err(format!("{}-bit floats are not supported", width)),
+            },
+            "OpTypeVector" => {
+                let scalar = self.type_name(operand_id(ops.get(0))?)?;
+                format!("{}vec{}", scalar_prefix(&scalar)?, operand_int(ops.get(1))?)
+            },
+            "OpTypeMatrix" => {
+                let column = self.type_name(operand_id(ops.get(0))?)?;
+                let count = operand_int(ops.get(1))?;
+
+                // The editor only has square matrices
+                if !column.ends_with(&format!("vec{}", count)) {
+                    return Err(format!("Matrices of {} {} columns are not supported", count, column));
+                }
+
+                let prefix = if column.starts_with('d') { "d" } else { "" };
+                format!("{}mat{}", prefix, count)
+            },
+            "OpTypePointer" => {
+                let storage = operand_enum(ops.get(0))?;
+                let pointee = operand_id(ops.get(1))?;
+                let pointer = match storage {
+                    "Input" => Pointer::Input(pointee),
+                    "Output" => Pointer::Output(pointee),
+                    _ => return Err(format!("{} pointers are not supported", storage)),
+                };
+
+                self.pointers.insert(id, pointer);
+                return Ok(());
+            },
+            "OpTypeVoid" | "OpTypeFunction" => return Ok(()),
+            _ => return Err(format!("Unsupported type declaration {}", inst.class)),
+        };
+
+        self.type_names.insert(id, name);
+        Ok(())
+    }
+
+    fn declare_constant(&mut self, inst: &Instruction) -> Result<(), String> {
+        let id = Decompiler::result(inst)?;
+        let type_name = self.type_name(operand_id(inst.operands.get(0))?)?;
+
+        let value = match inst.class.as_str() {
+            "OpConstantTrue" => Value::Bool(true),
+            "OpConstantFalse" => Value::Bool(false),
+            "OpConstant" => literal_value(inst.operands.get(1))?,
+            "OpConstantComposite" => {
+                let mut values = Vec::new();
+                for operand in &inst.operands[1..] {
+                    let component = operand_id(Some(operand))?;
+                    let (_, value) = self.constants.get(&component)
+                        .cloned()
+                        .ok_or(format!("Constant %{} is not defined", component))?;
+                    values.push(value);
+                }
+
+                Value::Array(values)
+            },
+            _ => return Err(format!("Unsupported constant {}", inst.class)),
+        };
+
+        self.constants.insert(id, (type_name, value));
+        Ok(())
+    }
+
+    fn declare_variable(&mut self, inst: &Instruction) -> Result<(), String> {
+        let id = Decompiler::result(inst)?;
+        let pointer = operand_id(inst.operands.get(0))?;
+
+        let (storage, pointee) = match self.pointers.get(&pointer) {
+            Some(&Pointer::Input(pointee)) => ("Input", pointee),
+            Some(&Pointer::Output(pointee)) => ("Output", pointee),
+            None => return Err(format!("Variable %{} has an unsupported storage class", id)),
+        };
+
+        let type_name = self.type_name(pointee)?;
+        self.variables.insert(id, Variable {
+            storage: storage,
+            type_name: type_name,
+        });
+
+        Ok(())
+    }
+
+    fn interface_props(&self, var: u32) -> Result<Map<String, Value>, String> {
+        let variable = self.variables.get(&var)
+            .ok_or(format!("Variable %{} is not defined", var))?;
+        let location = self.locations.get(&var)
+            .ok_or(format!("Variable %{} has no location", var))?;
+
+        let mut props = Map::new();
+        props.insert(String::from("location"), Value::U64(*location as u64));
+        props.insert(String::from("type"), Value::String(variable.type_name.clone()));
+        Ok(props)
+    }
+
+    fn load(&mut self, inst: &Instruction) -> Result<(), String> {
+        let id = Decompiler::result(inst)?;
+        let var = operand_id(inst.operands.get(1))?;
+
+        match self.variables.get(&var).map(|v| v.storage) {
+            Some("Input") => {},
+            _ => return Err(format!("Loading from %{} is not supported", var)),
+        }
+
+        let key = match self.inputs.get(&var).cloned() {
+            Some(key) => key,
+            None => {
+                let props = self.interface_props(var)?;
+                let key = self.add_node("Input", props);
+                self.inputs.insert(var, key);
+                key
+            },
+        };
+
+        self.values.insert(id, key);
+        Ok(())
+    }
+
+    fn store(&mut self, inst: &Instruction) -> Result<(), String> {
+        let var = operand_id(inst.operands.get(0))?;
+
+        match self.variables.get(&var).map(|v| v.storage) {
+            Some("Output") => {},
+            _ => return Err(format!("Storing to %{} is not supported", var)),
+        }
+
+        // A later store to the same variable replaces the value written
+        let key = match self.outputs.get(&var).cloned() {
+            Some(key) => {
+                self.edges.retain(|edge| edge.find("to").and_then(Value::as_u64) != Some(key));
+                key
+            },
+            None => {
+                let props = self.interface_props(var)?;
+                let key = self.add_node("Output", props);
+                self.outputs.insert(var, key);
+                key
+            },
+        };
+
+        let value = self.value(operand_id(inst.operands.get(1))?)?;
+        self.add_edge(value, key, 0);
+        Ok(())
+    }
+
+    fn operation(&mut self, inst: &Instruction) -> Result<(), String> {
+        let id = Decompiler::result(inst)?;
+
+        let key = match inst.class.as_str() {
+            "OpCompositeConstruct" => {
+                let type_name = self.type_name(operand_id(inst.operands.get(0))?)?;
+
+                let mut props = Map::new();
+                props.insert(String::from("type"), Value::String(type_name));
+
+                let key = self.add_node("Construct", props);
+                self.connect(key, &inst.operands[1..])?;
+                key
+            },
+            "OpCompositeExtract" => {
+                let mut key = self.value(operand_id(inst.operands.get(1))?)?;
+
+                for index in &inst.operands[2..] {
+                    let mut props = Map::new();
+                    props.insert(String::from("index"), Value::U64(operand_int(Some(index))? as u64));
+
+                    let extract = self.add_node("Extract", props);
+                    self.add_edge(key, extract, 0);
+                    key = extract;
+                }
+
+                key
+            },
+            "OpExtInst" => {
+                let name = match inst.operands.get(2) {
+                    Some(&Operand::ExtInst(ref name)) => name.clone(),
+                    _ => return Err(String::from("Only GLSL.std.450 extended instructions are supported")),
+                };
+
+                let title = glsl_title(&name)
+                    .ok_or(format!("Unsupported GLSL.std.450 instruction {}", name))?;
+
+                let key = self.add_node(title, Map::new());
+                self.connect(key, &inst.operands[3..])?;
+                key
+            },
+            _ => {
+                let title = node_title(inst)
+                    .ok_or(format!("Unsupported instruction {}", inst.class))?;
+
+                let key = self.add_node(title, Map::new());
+                self.connect(key, &inst.operands[1..])?;
+                key
+            },
+        };
+
+        self.values.insert(id, key);
+        Ok(())
+    }
+
+    fn instruction(&mut self, inst: &Instruction) -> Result<(), String> {
+        match inst.class.as_str() {
+            "OpCapability" | "OpExtInstImport" | "OpMemoryModel" |
+            "OpExecutionMode" | "OpSource" | "OpSourceContinued" |
+            "OpSourceExtension" | "OpName" | "OpMemberName" |
+            "OpString" | "OpLine" | "OpReturn" | "OpFunctionEnd" => {},
+
+            "OpEntryPoint" => {
+                if self.stage.is_some() {
+                    return Err(String::from("Modules with several entry points are not supported"));
+                }
+
+                self.stage = Some(match operand_enum(inst.operands.get(0))? {
+                    "Vertex" => "vertex",
+                    "Fragment" => "fragment",
+                    model => return Err(format!("{} shaders are not supported", model)),
+                });
+            },
+
+            "OpDecorate" => match operand_enum(inst.operands.get(1))? {
+                "Location" => {
+                    let target = operand_id(inst.operands.get(0))?;
+                    self.locations.insert(target, operand_int(inst.operands.get(2))?);
+                },
+                "BuiltIn" => return Err(String::from("Built-in variables are not supported")),
+                _ => {},
+            },
+
+            "OpFunction" => {
+                self.functions += 1;
+                if self.functions > 1 {
+                    return Err(String::from("Modules with several functions are not supported"));
+                }
+            },
+            "OpLabel" => {
+                self.labels += 1;
+                if self.labels > 1 {
+                    return Err(String::from("Control flow is not supported"));
+                }
+            },
+            "OpBranch" | "OpBranchConditional" | "OpSwitch" |
+            "OpSelectionMerge" | "OpLoopMerge" | "OpPhi" |
+            "OpKill" | "OpReturnValue" | "OpUnreachable" => {
+                return Err(format!("Control flow is not supported ({})", inst.class));
+            },
+            "OpFunctionCall" => return Err(String::from("Function calls are not supported")),
+
+            "OpConstantTrue" | "OpConstantFalse" |
+            "OpConstant" | "OpConstantComposite" => self.declare_constant(inst)?,
+
+            "OpVariable" => self.declare_variable(inst)?,
+            "OpLoad" => self.load(inst)?,
+            "OpStore" => self.store(inst)?,
+
+            class if class.starts_with("OpType") => self.declare_type(inst)?,
+
+            _ => self.operation(inst)?,
+        }
+
+        Ok(())
+    }
+}
+
+pub fn decompile(listing: &Listing) -> Result<Value, String> {
+    let mut decompiler = Decompiler::new();
+
+    for inst in &listing.instructions {
+        decompiler.instruction(inst)?;
+    }
+
+    let stage = decompiler.stage.ok_or("Module has no entry point")?;
+
+    let mut graph = Map::new();
+    graph.insert(String::from("version"), Value::U64(CURRENT_VERSION));
+    graph.insert(String::from("stage"), Value::String(String::from(stage)));
+    graph.insert(String::from("nodes"), Value::Object(decompiler.nodes));
+    graph.insert(String::from("edges"), Value::Array(decompiler.edges));
+
+    Ok(Value::Object(graph))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{to_string, Value};
+
+    use embed::push_instruction;
+    use printer::*;
+
+    use super::{decompile, node_title};
+
+    fn module(body: &[(u32, &[u32])]) -> Listing {
+        let mut words = vec![MAGIC_NUMBER, 0x00010000, 0, 32, 0];
+        for &(opcode, operands) in body {
+            push_instruction(&mut words, opcode, operands);
+        }
+
+        decode_words(&words).unwrap()
+    }
+
+    fn titles(graph: &Value) -> Vec<String> {
+        graph.find("nodes").and_then(Value::as_object).unwrap()
+            .values()
+            .filter_map(|node| node.find("title").and_then(Value::as_str))
+            .map(String::from)
+            .collect()
+    }
+
+    fn instruction(class: &str) -> Instruction {
+        Instruction {
+            opcode: 0,
+            class: String::from(class),
+            result_id: Some(1),
+            operands: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn remainders_are_not_modulus() {
+        assert_eq!(node_title(&instruction("OpFMod")), Some("Modulus"));
+        assert_eq!(node_title(&instruction("OpUMod")), Some("Modulus"));
+        assert_eq!(node_title(&instruction("OpFRem")), None);
+        assert_eq!(node_title(&instruction("OpSRem")), None);
+    }
+
+    #[test]
+    fn compiled_graphs_decompile_to_valid_graphs() {
+        let program = ::compile(include_str!("../tests/fixtures/normalize.json"), 0).unwrap();
+        let graph = decompile(&program.listing(0).unwrap()).unwrap();
+
+        assert_eq!(graph.find("stage").and_then(Value::as_str), Some("fragment"));
+        assert!(titles(&graph).contains(&String::from("Output")));
+
+        if let Err(diags) = ::parse_graph(&to_string(&graph).unwrap()) {
+            panic!("{:?}", diags);
+        }
+    }
+
+    #[test]
+    fn control_flow_is_rejected() {
+        let listing = module(&[
+            (19, &[1]),
+            (33, &[2, 1]),
+            (54, &[1, 3, 0, 2]),
+            (248, &[4]),
+            (249, &[5]),
+        ]);
+        assert_eq!(decompile(&listing).unwrap_err(), "Control flow is not supported (OpBranch)");
+
+        let listing = module(&[
+            (19, &[1]),
+            (33, &[2, 1]),
+            (54, &[1, 3, 0, 2]),
+            (248, &[4]),
+            (248, &[5]),
+        ]);
+        assert_eq!(decompile(&listing).unwrap_err(), "Control flow is not supported");
+    }
+
+    #[test]
+    fn matrices_must_be_square() {
+        let listing = module(&[
+            (22, &[1, 32]),
+            (23, &[2, 1, 3]),
+            (24, &[3, 2, 2]),
+        ]);
+        assert_eq!(decompile(&listing).unwrap_err(), "Matrices of 2 vec3 columns are not supported");
+    }
+
+    #[test]
+    fn stores_share_their_output() {
+        let listing = module(&[
+            (71, &[4, 30, 0]),
+            (22, &[1, 32]),
+            (32, &[2, 3, 1]),
+            (59, &[2, 4, 3]),
+            (43, &[1, 5, 0x3f800000]),
+            (43, &[1, 6, 0x40000000]),
+            (62, &[4, 5]),
+            (62, &[4, 6]),
+        ]);
+
+        let mut decompiler = super::Decompiler::new();
+        for inst in &listing.instructions {
+            decompiler.instruction(inst).unwrap();
+        }
+
+        let outputs: Vec<_> = decompiler.nodes.iter()
+            .filter(|&(_, node)| node.find("title").and_then(Value::as_str) == Some("Output"))
+            .map(|(key, _)| key.clone())
+            .collect();
+        assert_eq!(outputs.len(), 1);
+
+        // Only the last value written is connected
+        assert_eq!(decompiler.edges.len(), 1);
+        let from = decompiler.edges[0].find("from").and_then(Value::as_u64).unwrap();
+        let value = decompiler.nodes[&format!("{}", from)].find("value").and_then(Value::as_f64);
+        assert_eq!(value, Some(2.0));
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt;
+
+use serde_json::{Value, Map};
+
+/// Whether a diagnostic prevents the graph from compiling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The graph can't be compiled
+    Error,
+    /// The graph compiles but is likely not what was intended
+    Warning,
+}
+
+impl Severity {
+    /// Name of the severity in JSON output
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A message tied to the part of the editor graph it originates from, so the
+/// UI can mark the offending node or edge
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// Whether this is an error or a warning
+    pub severity: Severity,
+    /// Description of the problem
+    pub message: String,
+    /// Key of the node at fault
+    pub node: Option<String>,
+    /// Index of the edge at fault in the document
+    pub edge: Option<usize>,
+    /// Input of the node at fault
+    pub pin: Option<u32>,
+}
+
+impl Diagnostic {
+    /// Diagnostic that isn't tied to any part of the graph yet
+    pub fn new<S: Into<String>>(severity: Severity, message: S) -> Diagnostic {
+        Diagnostic {
+            severity: severity,
+            message: message.into(),
+            node: None,
+            edge: None,
+            pin: None,
+        }
+    }
+
+    /// New error
+    pub fn error<S: Into<String>>(message: S) -> Diagnostic {
+        Diagnostic::new(Severity::Error, message)
+    }
+
+    /// New warning
+    pub fn warning<S: Into<String>>(message: S) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, message)
+    }
+
+    /// Tie the diagnostic to a node
+    pub fn at_node<S: Into<String>>(mut self, key: S) -> Diagnostic {
+        self.node = Some(key.into());
+        self
+    }
+
+    /// Tie the diagnostic to an edge
+    pub fn at_edge(mut self, index: usize) -> Diagnostic {
+        self.edge = Some(index);
+        self
+    }
+
+    /// Tie the diagnostic to an input of its node
+    pub fn at_pin(mut self, pin: u32) -> Diagnostic {
+        self.pin = Some(pin);
+        self
+    }
+
+    /// JSON object sent to the editor
+    pub fn to_value(&self) -> Value {
+        let mut res = Map::new();
+
+        res.insert(String::from("severity"), Value::String(String::from(self.severity.as_str())));
+        res.insert(String::from("message"), Value::String(self.message.clone()));
+
+        if let Some(ref node) = self.node {
+            res.insert(String::from("node"), Value::String(node.clone()));
+        }
+        if let Some(edge) = self.edge {
+            res.insert(String::from("edge"), Value::U64(edge as u64));
+        }
+        if let Some(pin) = self.pin {
+            res.insert(String::from("pin"), Value::U64(pin as u64));
+        }
+
+        Value::Object(res)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.severity.as_str())?;
+
+        if let Some(ref node) = self.node {
+            write!(f, "node {}: ", node)?;
+        }
+        if let Some(edge) = self.edge {
+            write!(f, "edge {}: ", edge)?;
+        }
+        if let Some(pin) = self.pin {
+            write!(f, "pin {}: ", pin)?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<String> for Diagnostic {
+    fn from(message: String) -> Diagnostic {
+        Diagnostic::error(message)
+    }
+}
+
+impl<'a> From<&'a str> for Diagnostic {
+    fn from(message: &'a str) -> Diagnostic {
+        Diagnostic::error(message)
+    }
+}
@@ -0,0 +1,235 @@
+use std::cmp;
+
+use printer::*;
+
+// Name of the OpString used as the file operand of the embedded OpSource,
+// so the graph can be told apart from any other source in the module
+pub const GRAPH_FILE: &'static str = "rasen-editor.graph.json";
+
+const OP_SOURCE_CONTINUED: u32 = 2;
+const OP_SOURCE: u32 = 3;
+const OP_STRING: u32 = 7;
+
+// Instructions that must precede the debug section of a module
+pub const PREAMBLE: &'static [u32] = &[
+    10, // OpExtension
+    11, // OpExtInstImport
+    14, // OpMemoryModel
+    15, // OpEntryPoint
+    16, // OpExecutionMode
+    17, // OpCapability
+];
+
+// Words left for the string of an OpSource after its opcode, language,
+// version and file operands
+const MAX_SOURCE_WORDS: usize = 0xffff - 4;
+
+pub fn words_to_bytes(words: &[u32]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(words.len() * 4);
+    for word in words {
+        for i in 0..4 {
+            bytes.push((word >> (i * 8)) as u8);
+        }
+    }
+
+    bytes
+}
+
+pub fn encode_string(string: &str) -> Vec<u32> {
+    let bytes = string.as_bytes();
+    let mut words = vec![0u32; bytes.len() / 4 + 1];
+
+    for (i, byte) in bytes.iter().enumerate() {
+        words[i / 4] |= (*byte as u32) << ((i % 4) * 8);
+    }
+
+    words
+}
+
+pub fn push_instruction(res: &mut Vec<u32>, opcode: u32, operands: &[u32]) {
+    res.push(((operands.len() as u32 + 1) << 16) | opcode);
+    res.extend_from_slice(operands);
+}
+
+// Split the graph on char boundaries so that each piece fits in a single
+// OpSource or OpSourceContinued instruction
+fn source_chunks(source: &str) -> Vec<&str> {
+    let max_len = MAX_SOURCE_WORDS * 4 - 1;
+
+    let mut chunks = Vec::new();
+    let mut rest = source;
+    while !rest.is_empty() {
+        let mut len = cmp::min(rest.len(), max_len);
+        while !rest.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        let (chunk, tail) = rest.split_at(len);
+        chunks.push(chunk);
+        rest = tail;
+    }
+
+    chunks
+}
+
+// Word offset of the first instruction past the leading run of the given
+// opcodes, where a new section of the module can be inserted
+pub fn section_offset(words: &[u32], opcodes: &[u32]) -> Result<usize, String> {
+    if words.len() < 5 {
+        return Err(String::from("Module is too short to contain a SPIR-V header"));
+    }
+
+    let mut offset = 5;
+    while offset < words.len() {
+        let opcode = words[offset] & 0xffff;
+        let count = (words[offset] >> 16) as usize;
+
+        if count == 0 {
+            return Err(format!("Invalid instruction at word {}", offset));
+        }
+        if !opcodes.contains(&opcode) {
+            break;
+        }
+
+        offset += count;
+    }
+
+    Ok(offset)
+}
+
+// Insert the graph JSON as an OpSource with an unknown source language into
+// an assembled module, right before its debug instructions
+pub fn embed_graph(words: &[u32], graph: &str) -> Result<Vec<u32>, String> {
+    let offset = section_offset(words, PREAMBLE)?;
+    let file_id = words[3];
+
+    let mut debug = Vec::new();
+
+    let mut operands = vec![file_id];
+    operands.extend(encode_string(GRAPH_FILE));
+    push_instruction(&mut debug, OP_STRING, &operands);
+
+    let chunks = source_chunks(graph);
+    let mut chunks = chunks.iter();
+
+    let mut operands = vec![0, 0, file_id];
+    if let Some(chunk) = chunks.next() {
+        operands.extend(encode_string(chunk));
+    }
+    push_instruction(&mut debug, OP_SOURCE, &operands);
+
+    for chunk in chunks {
+        push_instruction(&mut debug, OP_SOURCE_CONTINUED, &encode_string(chunk));
+    }
+
+    let mut res = Vec::with_capacity(words.len() + debug.len());
+    res.extend_from_slice(&words[..offset]);
+    res.extend(debug);
+    res.extend_from_slice(&words[offset..]);
+
+    // The file name takes up a new id
+    res[3] = file_id + 1;
+
+    Ok(res)
+}
+
+// Recover the graph JSON embedded by embed_graph
+pub fn extract_graph(listing: &Listing) -> Result<String, String> {
+    let file_id = listing.instructions.iter()
+        .filter(|inst| inst.class == "OpString")
+        .filter(|inst| match inst.operands.get(0) {
+            Some(&Operand::LitString(ref name)) => name == GRAPH_FILE,
+            _ => false,
+        })
+        .filter_map(|inst| inst.result_id)
+        .next()
+        .ok_or("Module does not contain an embedded graph")?;
+
+    let mut graph: Option<String> = None;
+    for inst in &listing.instructions {
+        match (inst.class.as_str(), graph.is_some()) {
+            ("OpSource", false) => {
+                match inst.operands.get(2) {
+                    Some(&Operand::Id(id)) if id == file_id => {},
+                    _ => continue,
+                }
+
+                graph = Some(match inst.operands.get(3) {
+                    Some(&Operand::LitString(ref source)) => source.clone(),
+                    _ => String::new(),
+                });
+            },
+            ("OpSourceContinued", true) => {
+                if let Some(&Operand::LitString(ref source)) = inst.operands.get(0) {
+                    if let Some(ref mut graph) = graph {
+                        graph.push_str(source);
+                    }
+                }
+            },
+            (_, true) => break,
+            _ => {},
+        }
+    }
+
+    graph.ok_or(String::from("Module does not contain an embedded graph"))
+}
+
+#[cfg(test)]
+mod tests {
+    use printer::*;
+
+    use super::*;
+
+    fn module() -> Vec<u32> {
+        let mut words = vec![MAGIC_NUMBER, 0x00010000, 0, 4, 0];
+        push_instruction(&mut words, 17, &[1]);
+        push_instruction(&mut words, 14, &[0, 1]);
+        push_instruction(&mut words, 19, &[1]);
+        push_instruction(&mut words, 33, &[2, 1]);
+
+        words
+    }
+
+    fn round_trip(graph: &str) -> Listing {
+        let words = embed_graph(&module(), graph).unwrap();
+        let listing = decode_words(&words).unwrap();
+
+        assert_eq!(listing.header.bound, 5);
+        assert_eq!(extract_graph(&listing).unwrap(), graph);
+
+        listing
+    }
+
+    fn continued(listing: &Listing) -> usize {
+        listing.instructions.iter()
+            .filter(|inst| inst.class == "OpSourceContinued")
+            .count()
+    }
+
+    #[test]
+    fn short_graph() {
+        let listing = round_trip(r#"{"nodes":{},"edges":[]}"#);
+        assert_eq!(continued(&listing), 0);
+    }
+
+    #[test]
+    fn long_graph() {
+        let graph: String = (0..60000).map(|i| format!("{:07}\n", i)).collect();
+        let listing = round_trip(&graph);
+        assert_eq!(continued(&listing), 1);
+    }
+
+    #[test]
+    fn multibyte_graph() {
+        // Offset by one byte so a chunk boundary falls inside a character
+        let graph = format!("x{}", "é→𝄞".repeat(40000));
+        let listing = round_trip(&graph);
+        assert!(continued(&listing) >= 1);
+    }
+
+    #[test]
+    fn missing_graph() {
+        let listing = decode_words(&module()).unwrap();
+        assert!(extract_graph(&listing).is_err());
+    }
+}
@@ -0,0 +1,425 @@
+//! C interface used by the editor through node-ffi, every export takes its
+//! input as a C string or byte buffer and hands back a JSON string or byte
+//! array that must be released with `rasen_free_string` / `rasen_free_buffer`
+
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::{
+    c_char, c_void,
+};
+use std::slice;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+use serde_json::{to_string, Value, Map};
+
+use compiler::*;
+use debug::*;
+use decompiler::*;
+use diagnostic::*;
+use printer::*;
+use typeck::{NodeTypes, Type};
+
+fn convert_asm(input: String) -> Result<String, Vec<Diagnostic>> {
+    super::print_module(&input, 0)
+}
+
+fn convert_text(input: String, flags: u32) -> Result<String, Vec<Diagnostic>> {
+    let program = super::compile(&input, flags)?;
+    let text = program.listing(flags)?.to_text();
+
+    let mut result = Map::new();
+    result.insert(String::from("text"), Value::String(text));
+
+    to_string(&result).map_err(|err| print_failed(format!("{}", err)))
+}
+
+fn type_value(ty: Option<Type>) -> Value {
+    match ty {
+        Some(ty) => Value::String(format!("{}", ty)),
+        None => Value::Null,
+    }
+}
+
+fn types_value(node_types: &BTreeMap<String, NodeTypes>) -> Value {
+    let mut types = Map::new();
+    for (key, node) in node_types {
+        let mut res = Map::new();
+        res.insert(String::from("output"), type_value(node.output));
+        res.insert(String::from("inputs"), Value::Array(
+            node.inputs.iter()
+                .map(|ty| type_value(*ty))
+                .collect()
+        ));
+
+        types.insert(key.clone(), Value::Object(res));
+    }
+
+    Value::Object(types)
+}
+
+fn convert_types(input: String) -> Result<String, Vec<Diagnostic>> {
+    let (node_types, diags) = super::infer_types(&input)?;
+
+    let mut result = Map::new();
+    result.insert(String::from("types"), types_value(&node_types));
+    result.insert(String::from("diagnostics"), Value::Array(
+        diags.iter()
+            .map(Diagnostic::to_value)
+            .collect()
+    ));
+
+    to_string(&result).map_err(|err| print_failed(format!("{}", err)))
+}
+
+fn convert_upgrade(input: String) -> Result<String, Vec<Diagnostic>> {
+    let (graph, upgrade) = super::upgrade_graph(&input)?;
+
+    let mut result = Map::new();
+    result.insert(String::from("graph"), graph);
+    result.insert(String::from("from"), Value::U64(upgrade.from));
+    result.insert(String::from("to"), Value::U64(upgrade.to));
+    result.insert(String::from("changes"), Value::Array(
+        upgrade.changes.into_iter()
+            .map(Value::String)
+            .collect()
+    ));
+
+    to_string(&result).map_err(|err| print_failed(format!("{}", err)))
+}
+
+fn convert_spv(input: &[u8]) -> Result<String, Vec<Diagnostic>> {
+    let words = words_from_bytes(input).map_err(print_failed)?;
+    let listing = decode_words(&words).map_err(print_failed)?;
+
+    to_string(&listing.to_value()).map_err(|err| print_failed(format!("{}", err)))
+}
+
+fn convert_graph(input: &[u8]) -> Result<String, Vec<Diagnostic>> {
+    let words = words_from_bytes(input).map_err(print_failed)?;
+    let listing = decode_words(&words).map_err(print_failed)?;
+    let graph = decompile(&listing).map_err(|err| vec![Diagnostic::error(err)])?;
+
+    to_string(&graph).map_err(|err| print_failed(format!("{}", err)))
+}
+
+fn convert_source(input: &[u8]) -> Result<String, Vec<Diagnostic>> {
+    let words = words_from_bytes(input).map_err(print_failed)?;
+    let listing = decode_words(&words).map_err(print_failed)?;
+
+    ::embed::extract_graph(&listing).map_err(|err| vec![Diagnostic::error(err)])
+}
+
+fn convert_all(input: String, flags: u32) -> Result<String, Vec<Diagnostic>> {
+    let program = super::compile(&input, flags)?;
+    let listing = program.listing(flags)?;
+
+    let words = program.words.iter()
+        .map(|word| Value::U64(*word as u64))
+        .collect();
+
+    let warnings = program.parsed.warnings.iter()
+        .map(Diagnostic::to_value)
+        .collect();
+
+    let mut result = Map::new();
+    result.insert(String::from("assembly"), listing.to_value());
+    result.insert(String::from("bytecode"), Value::Array(words));
+    result.insert(String::from("sourceMap"), source_map_value(&program.source_map));
+    result.insert(String::from("types"), types_value(&program.parsed.types));
+    result.insert(String::from("warnings"), Value::Array(warnings));
+
+    to_string(&result).map_err(|err| print_failed(format!("{}", err)))
+}
+
+fn error_json(diags: Vec<Diagnostic>) -> String {
+    let message = diags.iter()
+        .map(|diag| format!("{}", diag))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut result = Map::new();
+    result.insert(String::from("error"), Value::String(message));
+    result.insert(String::from("diagnostics"), Value::Array(
+        diags.iter()
+            .map(Diagnostic::to_value)
+            .collect()
+    ));
+    to_string(&result).unwrap_or_else(|_| String::from("{\"error\":\"Unknown error\"}"))
+}
+
+fn panic_message(payload: Box<Any + Send>) -> String {
+    let msg = match payload.downcast_ref::<&str>() {
+        Some(msg) => String::from(*msg),
+        None => match payload.downcast_ref::<String>() {
+            Some(msg) => msg.clone(),
+            None => String::from("Box<Any>"),
+        },
+    };
+
+    format!("internal error: {}", msg)
+}
+
+// Make sure no panic can unwind across the FFI boundary
+fn catch<F, R>(convert: F) -> Result<R, Vec<Diagnostic>> where F: FnOnce() -> Result<R, Vec<Diagnostic>> {
+    match panic::catch_unwind(AssertUnwindSafe(convert)) {
+        Ok(res) => res,
+        Err(payload) => Err(vec![Diagnostic::error(panic_message(payload))]),
+    }
+}
+
+// Run a conversion with its input read from a C string
+fn guard<F, R>(input: *const c_char, convert: F) -> Result<R, Vec<Diagnostic>> where F: FnOnce(String) -> Result<R, Vec<Diagnostic>> {
+    if input.is_null() {
+        return Err(vec![Diagnostic::error("input is a null pointer")]);
+    }
+
+    catch(|| {
+        let input = unsafe {
+            CStr::from_ptr(input).to_string_lossy().into_owned()
+        };
+
+        convert(input)
+    })
+}
+
+// Run a conversion with its input read from a raw byte buffer
+fn guard_bytes<F, R>(data: *const u8, len: usize, convert: F) -> Result<R, Vec<Diagnostic>> where F: FnOnce(&[u8]) -> Result<R, Vec<Diagnostic>> {
+    if data.is_null() {
+        return Err(vec![Diagnostic::error("input is a null pointer")]);
+    }
+
+    catch(|| {
+        let input = unsafe {
+            slice::from_raw_parts(data, len)
+        };
+
+        convert(input)
+    })
+}
+
+fn into_c_string(result: Result<String, Vec<Diagnostic>>) -> *mut c_char {
+    let result = match result {
+        Ok(code) => code,
+        Err(diags) => error_json(diags)
+    };
+
+    let c_string = match CString::new(result) {
+        Ok(c_string) => c_string,
+        Err(err) => {
+            let result = String::from_utf8_lossy(&err.into_vec()).replace('\0', "\\u0000");
+            CString::new(result).unwrap_or_default()
+        },
+    };
+
+    c_string.into_raw()
+}
+
+/// Compile a graph and return its JSON listing
+#[no_mangle]
+pub extern fn rasen_to_assembly(input: *const c_char) -> *mut c_char {
+    into_c_string(guard(input, convert_asm))
+}
+
+/// Compile a graph and return its textual assembly
+#[no_mangle]
+pub extern fn rasen_to_spvasm(input: *const c_char) -> *mut c_char {
+    rasen_to_spvasm_ext(input, 0)
+}
+
+/// Compile a graph with `flags` and return its textual assembly
+#[no_mangle]
+pub extern fn rasen_to_spvasm_ext(input: *const c_char, flags: u32) -> *mut c_char {
+    into_c_string(guard(input, |input| convert_text(input, flags)))
+}
+
+/// Infer the types of a graph, along with its diagnostics
+#[no_mangle]
+pub extern fn rasen_infer_types(input: *const c_char) -> *mut c_char {
+    into_c_string(guard(input, convert_types))
+}
+
+/// Upgrade a graph document to the current version
+#[no_mangle]
+pub extern fn rasen_upgrade_graph(input: *const c_char) -> *mut c_char {
+    into_c_string(guard(input, convert_upgrade))
+}
+
+/// Return the JSON listing of a SPIR-V module
+#[no_mangle]
+pub extern fn rasen_disassemble(data: *const u8, len: usize) -> *mut c_char {
+    into_c_string(guard_bytes(data, len, convert_spv))
+}
+
+/// Decompile a SPIR-V module into a graph document
+#[no_mangle]
+pub extern fn rasen_to_graph(data: *const u8, len: usize) -> *mut c_char {
+    into_c_string(guard_bytes(data, len, convert_graph))
+}
+
+/// Return the graph document embedded in a SPIR-V module
+#[no_mangle]
+pub extern fn rasen_extract_graph(data: *const u8, len: usize) -> *mut c_char {
+    into_c_string(guard_bytes(data, len, convert_source))
+}
+
+/// Compile a graph and return its listing, module and warnings
+#[no_mangle]
+pub extern fn rasen_compile(input: *const c_char) -> *mut c_char {
+    rasen_compile_ext(input, 0)
+}
+
+/// Compile a graph with `flags` and return its listing, module and warnings
+#[no_mangle]
+pub extern fn rasen_compile_ext(input: *const c_char, flags: u32) -> *mut c_char {
+    into_c_string(guard(input, |input| convert_all(input, flags)))
+}
+
+/// Release a string returned by this library
+#[no_mangle]
+pub extern fn rasen_free_string(ptr: *mut c_char) {
+    if ptr.is_null() {
+        return;
+    }
+
+    unsafe {
+        CString::from_raw(ptr);
+    }
+}
+
+/// Whether an `Array` holds the compiled module or an error
+#[repr(C)]
+pub enum Status {
+    /// The data is a SPIR-V module
+    Ok = 0,
+    /// The data is an error JSON object
+    Error = 1,
+}
+
+/// Byte buffer returned to C, to be released with `rasen_free_buffer`
+#[repr(C)]
+pub struct Array {
+    status: Status,
+    len: usize,
+    data: *const c_void,
+}
+
+impl Array {
+    fn new(status: Status, data: Vec<u8>) -> *mut Array {
+        let len = data.len();
+        let data = Box::into_raw(data.into_boxed_slice());
+
+        Box::into_raw(Box::new(Array {
+            status: status,
+            len: len,
+            data: data as *const c_void,
+        }))
+    }
+}
+
+/// Compile a graph to a SPIR-V module
+#[no_mangle]
+pub extern fn rasen_to_bytecode(input: *const c_char) -> *mut Array {
+    rasen_to_bytecode_ext(input, 0)
+}
+
+/// Compile a graph with `flags` to a SPIR-V module
+#[no_mangle]
+pub extern fn rasen_to_bytecode_ext(input: *const c_char, flags: u32) -> *mut Array {
+    let bytes = guard(input, |input| {
+        super::compile(&input, flags).map(|program| program.bytes())
+    });
+
+    match bytes {
+        Ok(code) => Array::new(Status::Ok, code),
+        Err(diags) => Array::new(Status::Error, error_json(diags).into_bytes()),
+    }
+}
+
+/// Release an `Array` returned by this library
+#[no_mangle]
+pub extern fn rasen_free_buffer(ptr: *mut Array) {
+    if ptr.is_null() {
+        return;
+    }
+
+    unsafe {
+        let array = Box::from_raw(ptr);
+        let data = slice::from_raw_parts_mut(array.data as *mut u8, array.len);
+        Box::from_raw(data as *mut [u8]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+    use std::os::raw::c_char;
+
+    use serde_json::{from_str, Value};
+
+    use compiler::PANIC_LOCATION;
+
+    use super::{catch, rasen_compile, rasen_to_assembly, rasen_free_string};
+
+    fn call(export: extern fn(*const c_char) -> *mut c_char, input: &str) -> Value {
+        let input = CString::new(input).unwrap();
+
+        let output = export(input.as_ptr());
+        let result = unsafe {
+            CStr::from_ptr(output).to_string_lossy().into_owned()
+        };
+        rasen_free_string(output);
+
+        from_str(&result).unwrap()
+    }
+
+    #[test]
+    fn panics_become_errors() {
+        let res: Result<(), _> = catch(|| panic!("conversion failed"));
+        let diags = res.unwrap_err();
+
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].message.starts_with("internal error:"));
+        assert!(diags[0].message.contains("conversion failed"));
+    }
+
+    #[test]
+    fn undefined_nodes_return_error_json() {
+        let result = call(rasen_to_assembly, r#"{
+            "nodes": {
+                "0": { "title": "Input", "location": 0, "type": "vec4" }
+            },
+            "edges": [ { "from": 0, "to": 1, "input": 0 } ]
+        }"#);
+
+        let message = result.find("error").and_then(Value::as_str).unwrap();
+        assert!(message.contains("edge destination node is undefined"));
+    }
+
+    #[test]
+    fn build_panics_return_error_json() {
+        let result = call(rasen_to_assembly, &format!(r#"{{
+            "nodes": {{
+                "0": {{ "title": "Input", "location": 0, "type": "vec4" }},
+                "1": {{ "title": "Output", "location": {}, "type": "vec4" }}
+            }},
+            "edges": [ {{ "from": 0, "to": 1, "input": 0 }} ]
+        }}"#, PANIC_LOCATION));
+
+        let message = result.find("error").and_then(Value::as_str).unwrap();
+        assert!(message.contains("internal error: building output"), "{}", message);
+
+        let diagnostics = result.find("diagnostics").and_then(Value::as_array).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn compile_results_carry_types() {
+        let result = call(rasen_compile, include_str!("../tests/fixtures/normalize.json"));
+
+        let types = result.find("types").and_then(Value::as_object).unwrap();
+        let normalize = types["1"].as_object().unwrap();
+        assert_eq!(normalize["output"], Value::String(String::from("vec3")));
+        assert_eq!(normalize["inputs"], Value::Array(vec![Value::String(String::from("vec3"))]));
+    }
+}
@@ -0,0 +1,799 @@
+// Subset of the SPIR-V 1.0 unified grammar, covering every instruction
+// rasen can emit along with the common core instructions found in modules
+// produced by other tools
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnumKind {
+    SourceLanguage,
+    ExecutionModel,
+    AddressingModel,
+    MemoryModel,
+    ExecutionMode,
+    StorageClass,
+    Dim,
+    ImageFormat,
+    AccessQualifier,
+    FunctionControl,
+    MemoryAccess,
+    SelectionControl,
+    LoopControl,
+    ImageOperands,
+    Decoration,
+    BuiltIn,
+    FunctionParameterAttribute,
+    FPRoundingMode,
+    FPFastMathMode,
+    LinkageType,
+    Capability,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    IdResultType,
+    IdResult,
+    IdRef,
+    LiteralInteger,
+    LiteralString,
+    LiteralContextDependentNumber,
+    LiteralExtInstInteger,
+    LiteralSpecConstantOpInteger,
+    PairLiteralIntegerIdRef,
+    PairIdRefLiteralInteger,
+    PairIdRefIdRef,
+    Enum(EnumKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantifier {
+    One,
+    Optional,
+    Variadic,
+}
+
+pub struct OperandDesc {
+    pub kind: Kind,
+    pub quantifier: Quantifier,
+}
+
+pub struct InstructionDesc {
+    pub opcode: u16,
+    pub name: &'static str,
+    pub operands: &'static [OperandDesc],
+}
+
+pub struct Enumerant {
+    pub value: u32,
+    pub name: &'static str,
+    pub params: &'static [Kind],
+}
+
+macro_rules! operand {
+    ( ? $kind:expr ) => { OperandDesc { kind: $kind, quantifier: Quantifier::Optional } };
+    ( * $kind:expr ) => { OperandDesc { kind: $kind, quantifier: Quantifier::Variadic } };
+    ( $kind:expr ) => { OperandDesc { kind: $kind, quantifier: Quantifier::One } };
+}
+
+const RESULT_TYPE: OperandDesc = operand!(Kind::IdResultType);
+const RESULT: OperandDesc = operand!(Kind::IdResult);
+const ID: OperandDesc = operand!(Kind::IdRef);
+const OPT_ID: OperandDesc = operand!(? Kind::IdRef);
+const IDS: OperandDesc = operand!(* Kind::IdRef);
+const LIT: OperandDesc = operand!(Kind::LiteralInteger);
+const LITS: OperandDesc = operand!(* Kind::LiteralInteger);
+const STRING: OperandDesc = operand!(Kind::LiteralString);
+const OPT_STRING: OperandDesc = operand!(? Kind::LiteralString);
+
+macro_rules! enum_operand {
+    ( ? $kind:ident ) => { operand!(? Kind::Enum(EnumKind::$kind)) };
+    ( $kind:ident ) => { operand!(Kind::Enum(EnumKind::$kind)) };
+}
+
+macro_rules! inst {
+    ( $opcode:expr, $name:expr, [ $( $operand:expr ),* ] ) => {
+        InstructionDesc {
+            opcode: $opcode,
+            name: $name,
+            operands: &[ $( $operand ),* ],
+        }
+    }
+}
+
+pub static INSTRUCTIONS: &'static [InstructionDesc] = &[
+    inst!(0, "OpNop", []),
+    inst!(1, "OpUndef", [RESULT_TYPE, RESULT]),
+    inst!(2, "OpSourceContinued", [STRING]),
+    inst!(3, "OpSource", [enum_operand!(SourceLanguage), LIT, OPT_ID, OPT_STRING]),
+    inst!(4, "OpSourceExtension", [STRING]),
+    inst!(5, "OpName", [ID, STRING]),
+    inst!(6, "OpMemberName", [ID, LIT, STRING]),
+    inst!(7, "OpString", [RESULT, STRING]),
+    inst!(8, "OpLine", [ID, LIT, LIT]),
+    inst!(10, "OpExtension", [STRING]),
+    inst!(11, "OpExtInstImport", [RESULT, STRING]),
+    inst!(12, "OpExtInst", [RESULT_TYPE, RESULT, ID, operand!(Kind::LiteralExtInstInteger), IDS]),
+    inst!(14, "OpMemoryModel", [enum_operand!(AddressingModel), enum_operand!(MemoryModel)]),
+    inst!(15, "OpEntryPoint", [enum_operand!(ExecutionModel), ID, STRING, IDS]),
+    inst!(16, "OpExecutionMode", [ID, enum_operand!(ExecutionMode)]),
+    inst!(17, "OpCapability", [enum_operand!(Capability)]),
+
+    inst!(19, "OpTypeVoid", [RESULT]),
+    inst!(20, "OpTypeBool", [RESULT]),
+    inst!(21, "OpTypeInt", [RESULT, LIT, LIT]),
+    inst!(22, "OpTypeFloat", [RESULT, LIT]),
+    inst!(23, "OpTypeVector", [RESULT, ID, LIT]),
+    inst!(24, "OpTypeMatrix", [RESULT, ID, LIT]),
+    inst!(25, "OpTypeImage", [
+        RESULT, ID, enum_operand!(Dim), LIT, LIT, LIT, LIT,
+        enum_operand!(ImageFormat), enum_operand!(? AccessQualifier)
+    ]),
+    inst!(26, "OpTypeSampler", [RESULT]),
+    inst!(27, "OpTypeSampledImage", [RESULT, ID]),
+    inst!(28, "OpTypeArray", [RESULT, ID, ID]),
+    inst!(29, "OpTypeRuntimeArray", [RESULT, ID]),
+    inst!(30, "OpTypeStruct", [RESULT, IDS]),
+    inst!(31, "OpTypeOpaque", [RESULT, STRING]),
+    inst!(32, "OpTypePointer", [RESULT, enum_operand!(StorageClass), ID]),
+    inst!(33, "OpTypeFunction", [RESULT, ID, IDS]),
+    inst!(34, "OpTypeEvent", [RESULT]),
+    inst!(35, "OpTypeDeviceEvent", [RESULT]),
+    inst!(36, "OpTypeReserveId", [RESULT]),
+    inst!(37, "OpTypeQueue", [RESULT]),
+    inst!(38, "OpTypePipe", [RESULT, enum_operand!(AccessQualifier)]),
+    inst!(39, "OpTypeForwardPointer", [ID, enum_operand!(StorageClass)]),
+
+    inst!(41, "OpConstantTrue", [RESULT_TYPE, RESULT]),
+    inst!(42, "OpConstantFalse", [RESULT_TYPE, RESULT]),
+    inst!(43, "OpConstant", [RESULT_TYPE, RESULT, operand!(Kind::LiteralContextDependentNumber)]),
+    inst!(44, "OpConstantComposite", [RESULT_TYPE, RESULT, IDS]),
+    inst!(46, "OpConstantNull", [RESULT_TYPE, RESULT]),
+    inst!(48, "OpSpecConstantTrue", [RESULT_TYPE, RESULT]),
+    inst!(49, "OpSpecConstantFalse", [RESULT_TYPE, RESULT]),
+    inst!(50, "OpSpecConstant", [RESULT_TYPE, RESULT, operand!(Kind::LiteralContextDependentNumber)]),
+    inst!(51, "OpSpecConstantComposite", [RESULT_TYPE, RESULT, IDS]),
+    inst!(52, "OpSpecConstantOp", [RESULT_TYPE, RESULT, operand!(Kind::LiteralSpecConstantOpInteger), IDS]),
+
+    inst!(54, "OpFunction", [RESULT_TYPE, RESULT, enum_operand!(FunctionControl), ID]),
+    inst!(55, "OpFunctionParameter", [RESULT_TYPE, RESULT]),
+    inst!(56, "OpFunctionEnd", []),
+    inst!(57, "OpFunctionCall", [RESULT_TYPE, RESULT, ID, IDS]),
+
+    inst!(59, "OpVariable", [RESULT_TYPE, RESULT, enum_operand!(StorageClass), OPT_ID]),
+    inst!(60, "OpImageTexelPointer", [RESULT_TYPE, RESULT, ID, ID, ID]),
+    inst!(61, "OpLoad", [RESULT_TYPE, RESULT, ID, enum_operand!(? MemoryAccess)]),
+    inst!(62, "OpStore", [ID, ID, enum_operand!(? MemoryAccess)]),
+    inst!(63, "OpCopyMemory", [ID, ID, enum_operand!(? MemoryAccess)]),
+    inst!(64, "OpCopyMemorySized", [ID, ID, ID, enum_operand!(? MemoryAccess)]),
+    inst!(65, "OpAccessChain", [RESULT_TYPE, RESULT, ID, IDS]),
+    inst!(66, "OpInBoundsAccessChain", [RESULT_TYPE, RESULT, ID, IDS]),
+    inst!(67, "OpPtrAccessChain", [RESULT_TYPE, RESULT, ID, ID, IDS]),
+    inst!(68, "OpArrayLength", [RESULT_TYPE, RESULT, ID, LIT]),
+    inst!(69, "OpGenericPtrMemSemantics", [RESULT_TYPE, RESULT, ID]),
+    inst!(70, "OpInBoundsPtrAccessChain", [RESULT_TYPE, RESULT, ID, ID, IDS]),
+
+    inst!(71, "OpDecorate", [ID, enum_operand!(Decoration)]),
+    inst!(72, "OpMemberDecorate", [ID, LIT, enum_operand!(Decoration)]),
+    inst!(73, "OpDecorationGroup", [RESULT]),
+    inst!(74, "OpGroupDecorate", [ID, IDS]),
+    inst!(75, "OpGroupMemberDecorate", [ID, operand!(* Kind::PairIdRefLiteralInteger)]),
+
+    inst!(77, "OpVectorExtractDynamic", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(78, "OpVectorInsertDynamic", [RESULT_TYPE, RESULT, ID, ID, ID]),
+    inst!(79, "OpVectorShuffle", [RESULT_TYPE, RESULT, ID, ID, LITS]),
+    inst!(80, "OpCompositeConstruct", [RESULT_TYPE, RESULT, IDS]),
+    inst!(81, "OpCompositeExtract", [RESULT_TYPE, RESULT, ID, LITS]),
+    inst!(82, "OpCompositeInsert", [RESULT_TYPE, RESULT, ID, ID, LITS]),
+    inst!(83, "OpCopyObject", [RESULT_TYPE, RESULT, ID]),
+    inst!(84, "OpTranspose", [RESULT_TYPE, RESULT, ID]),
+
+    inst!(86, "OpSampledImage", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(87, "OpImageSampleImplicitLod", [RESULT_TYPE, RESULT, ID, ID, enum_operand!(? ImageOperands)]),
+    inst!(88, "OpImageSampleExplicitLod", [RESULT_TYPE, RESULT, ID, ID, enum_operand!(ImageOperands)]),
+    inst!(89, "OpImageSampleDrefImplicitLod", [RESULT_TYPE, RESULT, ID, ID, ID, enum_operand!(? ImageOperands)]),
+    inst!(90, "OpImageSampleDrefExplicitLod", [RESULT_TYPE, RESULT, ID, ID, ID, enum_operand!(ImageOperands)]),
+    inst!(91, "OpImageSampleProjImplicitLod", [RESULT_TYPE, RESULT, ID, ID, enum_operand!(? ImageOperands)]),
+    inst!(92, "OpImageSampleProjExplicitLod", [RESULT_TYPE, RESULT, ID, ID, enum_operand!(ImageOperands)]),
+    inst!(93, "OpImageSampleProjDrefImplicitLod", [RESULT_TYPE, RESULT, ID, ID, ID, enum_operand!(? ImageOperands)]),
+    inst!(94, "OpImageSampleProjDrefExplicitLod", [RESULT_TYPE, RESULT, ID, ID, ID, enum_operand!(ImageOperands)]),
+    inst!(95, "OpImageFetch", [RESULT_TYPE, RESULT, ID, ID, enum_operand!(? ImageOperands)]),
+    inst!(96, "OpImageGather", [RESULT_TYPE, RESULT, ID, ID, ID, enum_operand!(? ImageOperands)]),
+    inst!(97, "OpImageDrefGather", [RESULT_TYPE, RESULT, ID, ID, ID, enum_operand!(? ImageOperands)]),
+    inst!(98, "OpImageRead", [RESULT_TYPE, RESULT, ID, ID, enum_operand!(? ImageOperands)]),
+    inst!(99, "OpImageWrite", [ID, ID, ID, enum_operand!(? ImageOperands)]),
+    inst!(100, "OpImage", [RESULT_TYPE, RESULT, ID]),
+    inst!(101, "OpImageQueryFormat", [RESULT_TYPE, RESULT, ID]),
+    inst!(102, "OpImageQueryOrder", [RESULT_TYPE, RESULT, ID]),
+    inst!(103, "OpImageQuerySizeLod", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(104, "OpImageQuerySize", [RESULT_TYPE, RESULT, ID]),
+    inst!(105, "OpImageQueryLod", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(106, "OpImageQueryLevels", [RESULT_TYPE, RESULT, ID]),
+    inst!(107, "OpImageQuerySamples", [RESULT_TYPE, RESULT, ID]),
+
+    inst!(109, "OpConvertFToU", [RESULT_TYPE, RESULT, ID]),
+    inst!(110, "OpConvertFToS", [RESULT_TYPE, RESULT, ID]),
+    inst!(111, "OpConvertSToF", [RESULT_TYPE, RESULT, ID]),
+    inst!(112, "OpConvertUToF", [RESULT_TYPE, RESULT, ID]),
+    inst!(113, "OpUConvert", [RESULT_TYPE, RESULT, ID]),
+    inst!(114, "OpSConvert", [RESULT_TYPE, RESULT, ID]),
+    inst!(115, "OpFConvert", [RESULT_TYPE, RESULT, ID]),
+    inst!(116, "OpQuantizeToF16", [RESULT_TYPE, RESULT, ID]),
+    inst!(117, "OpConvertPtrToU", [RESULT_TYPE, RESULT, ID]),
+    inst!(118, "OpSatConvertSToU", [RESULT_TYPE, RESULT, ID]),
+    inst!(119, "OpSatConvertUToS", [RESULT_TYPE, RESULT, ID]),
+    inst!(120, "OpConvertUToPtr", [RESULT_TYPE, RESULT, ID]),
+    inst!(121, "OpPtrCastToGeneric", [RESULT_TYPE, RESULT, ID]),
+    inst!(122, "OpGenericCastToPtr", [RESULT_TYPE, RESULT, ID]),
+    inst!(123, "OpGenericCastToPtrExplicit", [RESULT_TYPE, RESULT, ID, enum_operand!(StorageClass)]),
+    inst!(124, "OpBitcast", [RESULT_TYPE, RESULT, ID]),
+
+    inst!(126, "OpSNegate", [RESULT_TYPE, RESULT, ID]),
+    inst!(127, "OpFNegate", [RESULT_TYPE, RESULT, ID]),
+    inst!(128, "OpIAdd", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(129, "OpFAdd", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(130, "OpISub", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(131, "OpFSub", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(132, "OpIMul", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(133, "OpFMul", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(134, "OpUDiv", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(135, "OpSDiv", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(136, "OpFDiv", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(137, "OpUMod", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(138, "OpSRem", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(139, "OpSMod", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(140, "OpFRem", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(141, "OpFMod", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(142, "OpVectorTimesScalar", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(143, "OpMatrixTimesScalar", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(144, "OpVectorTimesMatrix", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(145, "OpMatrixTimesVector", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(146, "OpMatrixTimesMatrix", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(147, "OpOuterProduct", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(148, "OpDot", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(149, "OpIAddCarry", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(150, "OpISubBorrow", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(151, "OpUMulExtended", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(152, "OpSMulExtended", [RESULT_TYPE, RESULT, ID, ID]),
+
+    inst!(154, "OpAny", [RESULT_TYPE, RESULT, ID]),
+    inst!(155, "OpAll", [RESULT_TYPE, RESULT, ID]),
+    inst!(156, "OpIsNan", [RESULT_TYPE, RESULT, ID]),
+    inst!(157, "OpIsInf", [RESULT_TYPE, RESULT, ID]),
+    inst!(158, "OpIsFinite", [RESULT_TYPE, RESULT, ID]),
+    inst!(159, "OpIsNormal", [RESULT_TYPE, RESULT, ID]),
+    inst!(160, "OpSignBitSet", [RESULT_TYPE, RESULT, ID]),
+    inst!(161, "OpLessOrGreater", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(162, "OpOrdered", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(163, "OpUnordered", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(164, "OpLogicalEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(165, "OpLogicalNotEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(166, "OpLogicalOr", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(167, "OpLogicalAnd", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(168, "OpLogicalNot", [RESULT_TYPE, RESULT, ID]),
+    inst!(169, "OpSelect", [RESULT_TYPE, RESULT, ID, ID, ID]),
+    inst!(170, "OpIEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(171, "OpINotEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(172, "OpUGreaterThan", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(173, "OpSGreaterThan", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(174, "OpUGreaterThanEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(175, "OpSGreaterThanEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(176, "OpULessThan", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(177, "OpSLessThan", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(178, "OpULessThanEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(179, "OpSLessThanEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(180, "OpFOrdEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(181, "OpFUnordEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(182, "OpFOrdNotEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(183, "OpFUnordNotEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(184, "OpFOrdLessThan", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(185, "OpFUnordLessThan", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(186, "OpFOrdGreaterThan", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(187, "OpFUnordGreaterThan", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(188, "OpFOrdLessThanEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(189, "OpFUnordLessThanEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(190, "OpFOrdGreaterThanEqual", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(191, "OpFUnordGreaterThanEqual", [RESULT_TYPE, RESULT, ID, ID]),
+
+    inst!(194, "OpShiftRightLogical", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(195, "OpShiftRightArithmetic", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(196, "OpShiftLeftLogical", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(197, "OpBitwiseOr", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(198, "OpBitwiseXor", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(199, "OpBitwiseAnd", [RESULT_TYPE, RESULT, ID, ID]),
+    inst!(200, "OpNot", [RESULT_TYPE, RESULT, ID]),
+    inst!(201, "OpBitFieldInsert", [RESULT_TYPE, RESULT, ID, ID, ID, ID]),
+    inst!(202, "OpBitFieldSExtract", [RESULT_TYPE, RESULT, ID, ID, ID]),
+    inst!(203, "OpBitFieldUExtract", [RESULT_TYPE, RESULT, ID, ID, ID]),
+    inst!(204, "OpBitReverse", [RESULT_TYPE, RESULT, ID]),
+    inst!(205, "OpBitCount", [RESULT_TYPE, RESULT, ID]),
+
+    inst!(207, "OpDPdx", [RESULT_TYPE, RESULT, ID]),
+    inst!(208, "OpDPdy", [RESULT_TYPE, RESULT, ID]),
+    inst!(209, "OpFwidth", [RESULT_TYPE, RESULT, ID]),
+    inst!(210, "OpDPdxFine", [RESULT_TYPE, RESULT, ID]),
+    inst!(211, "OpDPdyFine", [RESULT_TYPE, RESULT, ID]),
+    inst!(212, "OpFwidthFine", [RESULT_TYPE, RESULT, ID]),
+    inst!(213, "OpDPdxCoarse", [RESULT_TYPE, RESULT, ID]),
+    inst!(214, "OpDPdyCoarse", [RESULT_TYPE, RESULT, ID]),
+    inst!(215, "OpFwidthCoarse", [RESULT_TYPE, RESULT, ID]),
+
+    inst!(218, "OpEmitVertex", []),
+    inst!(219, "OpEndPrimitive", []),
+    inst!(220, "OpEmitStreamVertex", [ID]),
+    inst!(221, "OpEndStreamPrimitive", [ID]),
+    inst!(224, "OpControlBarrier", [ID, ID, ID]),
+    inst!(225, "OpMemoryBarrier", [ID, ID]),
+
+    inst!(227, "OpAtomicLoad", [RESULT_TYPE, RESULT, ID, ID, ID]),
+    inst!(228, "OpAtomicStore", [ID, ID, ID, ID]),
+    inst!(229, "OpAtomicExchange", [RESULT_TYPE, RESULT, ID, ID, ID, ID]),
+    inst!(230, "OpAtomicCompareExchange", [RESULT_TYPE, RESULT, ID, ID, ID, ID, ID, ID]),
+    inst!(231, "OpAtomicCompareExchangeWeak", [RESULT_TYPE, RESULT, ID, ID, ID, ID, ID, ID]),
+    inst!(232, "OpAtomicIIncrement", [RESULT_TYPE, RESULT, ID, ID, ID]),
+    inst!(233, "OpAtomicIDecrement", [RESULT_TYPE, RESULT, ID, ID, ID]),
+    inst!(234, "OpAtomicIAdd", [RESULT_TYPE, RESULT, ID, ID, ID, ID]),
+    inst!(235, "OpAtomicISub", [RESULT_TYPE, RESULT, ID, ID, ID, ID]),
+    inst!(236, "OpAtomicSMin", [RESULT_TYPE, RESULT, ID, ID, ID, ID]),
+    inst!(237, "OpAtomicUMin", [RESULT_TYPE, RESULT, ID, ID, ID, ID]),
+    inst!(238, "OpAtomicSMax", [RESULT_TYPE, RESULT, ID, ID, ID, ID]),
+    inst!(239, "OpAtomicUMax", [RESULT_TYPE, RESULT, ID, ID, ID, ID]),
+    inst!(240, "OpAtomicAnd", [RESULT_TYPE, RESULT, ID, ID, ID, ID]),
+    inst!(241, "OpAtomicOr", [RESULT_TYPE, RESULT, ID, ID, ID, ID]),
+    inst!(242, "OpAtomicXor", [RESULT_TYPE, RESULT, ID, ID, ID, ID]),
+
+    inst!(245, "OpPhi", [RESULT_TYPE, RESULT, operand!(* Kind::PairIdRefIdRef)]),
+    inst!(246, "OpLoopMerge", [ID, ID, enum_operand!(LoopControl)]),
+    inst!(247, "OpSelectionMerge", [ID, enum_operand!(SelectionControl)]),
+    inst!(248, "OpLabel", [RESULT]),
+    inst!(249, "OpBranch", [ID]),
+    inst!(250, "OpBranchConditional", [ID, ID, ID, LITS]),
+    inst!(251, "OpSwitch", [ID, ID, operand!(* Kind::PairLiteralIntegerIdRef)]),
+    inst!(252, "OpKill", []),
+    inst!(253, "OpReturn", []),
+    inst!(254, "OpReturnValue", [ID]),
+    inst!(255, "OpUnreachable", []),
+    inst!(256, "OpLifetimeStart", [ID, LIT]),
+    inst!(257, "OpLifetimeStop", [ID, LIT]),
+];
+
+pub fn instruction(opcode: u16) -> Option<&'static InstructionDesc> {
+    INSTRUCTIONS.iter().find(|desc| desc.opcode == opcode)
+}
+
+// Opcodes declaring a type, whose result ids are tagged as types when used
+pub fn is_type_declaration(opcode: u16) -> bool {
+    opcode >= 19 && opcode <= 39
+}
+
+macro_rules! enumerant {
+    ( $value:expr, $name:expr, [ $( $param:expr ),* ] ) => {
+        Enumerant { value: $value, name: $name, params: &[ $( $param ),* ] }
+    };
+    ( $value:expr, $name:expr ) => {
+        Enumerant { value: $value, name: $name, params: &[] }
+    };
+}
+
+const SOURCE_LANGUAGE: &'static [Enumerant] = &[
+    enumerant!(0, "Unknown"),
+    enumerant!(1, "ESSL"),
+    enumerant!(2, "GLSL"),
+    enumerant!(3, "OpenCL_C"),
+    enumerant!(4, "OpenCL_CPP"),
+];
+
+const EXECUTION_MODEL: &'static [Enumerant] = &[
+    enumerant!(0, "Vertex"),
+    enumerant!(1, "TessellationControl"),
+    enumerant!(2, "TessellationEvaluation"),
+    enumerant!(3, "Geometry"),
+    enumerant!(4, "Fragment"),
+    enumerant!(5, "GLCompute"),
+    enumerant!(6, "Kernel"),
+];
+
+const ADDRESSING_MODEL: &'static [Enumerant] = &[
+    enumerant!(0, "Logical"),
+    enumerant!(1, "Physical32"),
+    enumerant!(2, "Physical64"),
+];
+
+const MEMORY_MODEL: &'static [Enumerant] = &[
+    enumerant!(0, "Simple"),
+    enumerant!(1, "GLSL450"),
+    enumerant!(2, "OpenCL"),
+];
+
+const EXECUTION_MODE: &'static [Enumerant] = &[
+    enumerant!(0, "Invocations", [Kind::LiteralInteger]),
+    enumerant!(1, "SpacingEqual"),
+    enumerant!(2, "SpacingFractionalEven"),
+    enumerant!(3, "SpacingFractionalOdd"),
+    enumerant!(4, "VertexOrderCw"),
+    enumerant!(5, "VertexOrderCcw"),
+    enumerant!(6, "PixelCenterInteger"),
+    enumerant!(7, "OriginUpperLeft"),
+    enumerant!(8, "OriginLowerLeft"),
+    enumerant!(9, "EarlyFragmentTests"),
+    enumerant!(10, "PointMode"),
+    enumerant!(11, "Xfb"),
+    enumerant!(12, "DepthReplacing"),
+    enumerant!(14, "DepthGreater"),
+    enumerant!(15, "DepthLess"),
+    enumerant!(16, "DepthUnchanged"),
+    enumerant!(17, "LocalSize", [Kind::LiteralInteger, Kind::LiteralInteger, Kind::LiteralInteger]),
+    enumerant!(18, "LocalSizeHint", [Kind::LiteralInteger, Kind::LiteralInteger, Kind::LiteralInteger]),
+    enumerant!(19, "InputPoints"),
+    enumerant!(20, "InputLines"),
+    enumerant!(21, "InputLinesAdjacency"),
+    enumerant!(22, "Triangles"),
+    enumerant!(23, "InputTrianglesAdjacency"),
+    enumerant!(24, "Quads"),
+    enumerant!(25, "Isolines"),
+    enumerant!(26, "OutputVertices", [Kind::LiteralInteger]),
+    enumerant!(27, "OutputPoints"),
+    enumerant!(28, "OutputLineStrip"),
+    enumerant!(29, "OutputTriangleStrip"),
+    enumerant!(30, "VecTypeHint", [Kind::LiteralInteger]),
+    enumerant!(31, "ContractionOff"),
+];
+
+const STORAGE_CLASS: &'static [Enumerant] = &[
+    enumerant!(0, "UniformConstant"),
+    enumerant!(1, "Input"),
+    enumerant!(2, "Uniform"),
+    enumerant!(3, "Output"),
+    enumerant!(4, "Workgroup"),
+    enumerant!(5, "CrossWorkgroup"),
+    enumerant!(6, "Private"),
+    enumerant!(7, "Function"),
+    enumerant!(8, "Generic"),
+    enumerant!(9, "PushConstant"),
+    enumerant!(10, "AtomicCounter"),
+    enumerant!(11, "Image"),
+];
+
+const DIM: &'static [Enumerant] = &[
+    enumerant!(0, "1D"),
+    enumerant!(1, "2D"),
+    enumerant!(2, "3D"),
+    enumerant!(3, "Cube"),
+    enumerant!(4, "Rect"),
+    enumerant!(5, "Buffer"),
+    enumerant!(6, "SubpassData"),
+];
+
+const IMAGE_FORMAT: &'static [Enumerant] = &[
+    enumerant!(0, "Unknown"),
+    enumerant!(1, "Rgba32f"),
+    enumerant!(2, "Rgba16f"),
+    enumerant!(3, "R32f"),
+    enumerant!(4, "Rgba8"),
+    enumerant!(5, "Rgba8Snorm"),
+    enumerant!(6, "Rg32f"),
+    enumerant!(7, "Rg16f"),
+    enumerant!(8, "R11fG11fB10f"),
+    enumerant!(9, "R16f"),
+    enumerant!(10, "Rgba16"),
+    enumerant!(11, "Rgb10A2"),
+    enumerant!(12, "Rg16"),
+    enumerant!(13, "Rg8"),
+    enumerant!(14, "R16"),
+    enumerant!(15, "R8"),
+    enumerant!(16, "Rgba16Snorm"),
+    enumerant!(17, "Rg16Snorm"),
+    enumerant!(18, "Rg8Snorm"),
+    enumerant!(19, "R16Snorm"),
+    enumerant!(20, "R8Snorm"),
+    enumerant!(21, "Rgba32i"),
+    enumerant!(22, "Rgba16i"),
+    enumerant!(23, "Rgba8i"),
+    enumerant!(24, "R32i"),
+    enumerant!(25, "Rg32i"),
+    enumerant!(26, "Rg16i"),
+    enumerant!(27, "Rg8i"),
+    enumerant!(28, "R16i"),
+    enumerant!(29, "R8i"),
+    enumerant!(30, "Rgba32ui"),
+    enumerant!(31, "Rgba16ui"),
+    enumerant!(32, "Rgba8ui"),
+    enumerant!(33, "R32ui"),
+    enumerant!(34, "Rgb10a2ui"),
+    enumerant!(35, "Rg32ui"),
+    enumerant!(36, "Rg16ui"),
+    enumerant!(37, "Rg8ui"),
+    enumerant!(38, "R16ui"),
+    enumerant!(39, "R8ui"),
+];
+
+const ACCESS_QUALIFIER: &'static [Enumerant] = &[
+    enumerant!(0, "ReadOnly"),
+    enumerant!(1, "WriteOnly"),
+    enumerant!(2, "ReadWrite"),
+];
+
+const FUNCTION_CONTROL: &'static [Enumerant] = &[
+    enumerant!(0x0, "None"),
+    enumerant!(0x1, "Inline"),
+    enumerant!(0x2, "DontInline"),
+    enumerant!(0x4, "Pure"),
+    enumerant!(0x8, "Const"),
+];
+
+const MEMORY_ACCESS: &'static [Enumerant] = &[
+    enumerant!(0x0, "None"),
+    enumerant!(0x1, "Volatile"),
+    enumerant!(0x2, "Aligned", [Kind::LiteralInteger]),
+    enumerant!(0x4, "Nontemporal"),
+];
+
+const SELECTION_CONTROL: &'static [Enumerant] = &[
+    enumerant!(0x0, "None"),
+    enumerant!(0x1, "Flatten"),
+    enumerant!(0x2, "DontFlatten"),
+];
+
+const LOOP_CONTROL: &'static [Enumerant] = &[
+    enumerant!(0x0, "None"),
+    enumerant!(0x1, "Unroll"),
+    enumerant!(0x2, "DontUnroll"),
+];
+
+const IMAGE_OPERANDS: &'static [Enumerant] = &[
+    enumerant!(0x0, "None"),
+    enumerant!(0x1, "Bias", [Kind::IdRef]),
+    enumerant!(0x2, "Lod", [Kind::IdRef]),
+    enumerant!(0x4, "Grad", [Kind::IdRef, Kind::IdRef]),
+    enumerant!(0x8, "ConstOffset", [Kind::IdRef]),
+    enumerant!(0x10, "Offset", [Kind::IdRef]),
+    enumerant!(0x20, "ConstOffsets", [Kind::IdRef]),
+    enumerant!(0x40, "Sample", [Kind::IdRef]),
+    enumerant!(0x80, "MinLod", [Kind::IdRef]),
+];
+
+const DECORATION: &'static [Enumerant] = &[
+    enumerant!(0, "RelaxedPrecision"),
+    enumerant!(1, "SpecId", [Kind::LiteralInteger]),
+    enumerant!(2, "Block"),
+    enumerant!(3, "BufferBlock"),
+    enumerant!(4, "RowMajor"),
+    enumerant!(5, "ColMajor"),
+    enumerant!(6, "ArrayStride", [Kind::LiteralInteger]),
+    enumerant!(7, "MatrixStride", [Kind::LiteralInteger]),
+    enumerant!(8, "GLSLShared"),
+    enumerant!(9, "GLSLPacked"),
+    enumerant!(10, "CPacked"),
+    enumerant!(11, "BuiltIn", [Kind::Enum(EnumKind::BuiltIn)]),
+    enumerant!(13, "NoPerspective"),
+    enumerant!(14, "Flat"),
+    enumerant!(15, "Patch"),
+    enumerant!(16, "Centroid"),
+    enumerant!(17, "Sample"),
+    enumerant!(18, "Invariant"),
+    enumerant!(19, "Restrict"),
+    enumerant!(20, "Aliased"),
+    enumerant!(21, "Volatile"),
+    enumerant!(22, "Constant"),
+    enumerant!(23, "Coherent"),
+    enumerant!(24, "NonWritable"),
+    enumerant!(25, "NonReadable"),
+    enumerant!(26, "Uniform"),
+    enumerant!(28, "SaturatedConversion"),
+    enumerant!(29, "Stream", [Kind::LiteralInteger]),
+    enumerant!(30, "Location", [Kind::LiteralInteger]),
+    enumerant!(31, "Component", [Kind::LiteralInteger]),
+    enumerant!(32, "Index", [Kind::LiteralInteger]),
+    enumerant!(33, "Binding", [Kind::LiteralInteger]),
+    enumerant!(34, "DescriptorSet", [Kind::LiteralInteger]),
+    enumerant!(35, "Offset", [Kind::LiteralInteger]),
+    enumerant!(36, "XfbBuffer", [Kind::LiteralInteger]),
+    enumerant!(37, "XfbStride", [Kind::LiteralInteger]),
+    enumerant!(38, "FuncParamAttr", [Kind::Enum(EnumKind::FunctionParameterAttribute)]),
+    enumerant!(39, "FPRoundingMode", [Kind::Enum(EnumKind::FPRoundingMode)]),
+    enumerant!(40, "FPFastMathMode", [Kind::Enum(EnumKind::FPFastMathMode)]),
+    enumerant!(41, "LinkageAttributes", [Kind::LiteralString, Kind::Enum(EnumKind::LinkageType)]),
+    enumerant!(42, "NoContraction"),
+    enumerant!(43, "InputAttachmentIndex", [Kind::LiteralInteger]),
+    enumerant!(44, "Alignment", [Kind::LiteralInteger]),
+];
+
+const BUILT_IN: &'static [Enumerant] = &[
+    enumerant!(0, "Position"),
+    enumerant!(1, "PointSize"),
+    enumerant!(3, "ClipDistance"),
+    enumerant!(4, "CullDistance"),
+    enumerant!(5, "VertexId"),
+    enumerant!(6, "InstanceId"),
+    enumerant!(7, "PrimitiveId"),
+    enumerant!(8, "InvocationId"),
+    enumerant!(9, "Layer"),
+    enumerant!(10, "ViewportIndex"),
+    enumerant!(11, "TessLevelOuter"),
+    enumerant!(12, "TessLevelInner"),
+    enumerant!(13, "TessCoord"),
+    enumerant!(14, "PatchVertices"),
+    enumerant!(15, "FragCoord"),
+    enumerant!(16, "PointCoord"),
+    enumerant!(17, "FrontFacing"),
+    enumerant!(18, "SampleId"),
+    enumerant!(19, "SamplePosition"),
+    enumerant!(20, "SampleMask"),
+    enumerant!(22, "FragDepth"),
+    enumerant!(23, "HelperInvocation"),
+    enumerant!(24, "NumWorkgroups"),
+    enumerant!(25, "WorkgroupSize"),
+    enumerant!(26, "WorkgroupId"),
+    enumerant!(27, "LocalInvocationId"),
+    enumerant!(28, "GlobalInvocationId"),
+    enumerant!(29, "LocalInvocationIndex"),
+    enumerant!(30, "WorkDim"),
+    enumerant!(31, "GlobalSize"),
+    enumerant!(32, "EnqueuedWorkgroupSize"),
+    enumerant!(33, "GlobalOffset"),
+    enumerant!(34, "GlobalLinearId"),
+    enumerant!(36, "SubgroupSize"),
+    enumerant!(37, "SubgroupMaxSize"),
+    enumerant!(38, "NumSubgroups"),
+    enumerant!(39, "NumEnqueuedSubgroups"),
+    enumerant!(40, "SubgroupId"),
+    enumerant!(41, "SubgroupLocalInvocationId"),
+    enumerant!(42, "VertexIndex"),
+    enumerant!(43, "InstanceIndex"),
+];
+
+const FUNCTION_PARAMETER_ATTRIBUTE: &'static [Enumerant] = &[
+    enumerant!(0, "Zext"),
+    enumerant!(1, "Sext"),
+    enumerant!(2, "ByVal"),
+    enumerant!(3, "Sret"),
+    enumerant!(4, "NoAlias"),
+    enumerant!(5, "NoCapture"),
+    enumerant!(6, "NoWrite"),
+    enumerant!(7, "NoReadWrite"),
+];
+
+const FP_ROUNDING_MODE: &'static [Enumerant] = &[
+    enumerant!(0, "RTE"),
+    enumerant!(1, "RTZ"),
+    enumerant!(2, "RTP"),
+    enumerant!(3, "RTN"),
+];
+
+const FP_FAST_MATH_MODE: &'static [Enumerant] = &[
+    enumerant!(0x0, "None"),
+    enumerant!(0x1, "NotNaN"),
+    enumerant!(0x2, "NotInf"),
+    enumerant!(0x4, "NSZ"),
+    enumerant!(0x8, "AllowRecip"),
+    enumerant!(0x10, "Fast"),
+];
+
+const LINKAGE_TYPE: &'static [Enumerant] = &[
+    enumerant!(0, "Export"),
+    enumerant!(1, "Import"),
+];
+
+const CAPABILITY: &'static [Enumerant] = &[
+    enumerant!(0, "Matrix"),
+    enumerant!(1, "Shader"),
+    enumerant!(2, "Geometry"),
+    enumerant!(3, "Tessellation"),
+    enumerant!(4, "Addresses"),
+    enumerant!(5, "Linkage"),
+    enumerant!(6, "Kernel"),
+    enumerant!(7, "Vector16"),
+    enumerant!(8, "Float16Buffer"),
+    enumerant!(9, "Float16"),
+    enumerant!(10, "Float64"),
+    enumerant!(11, "Int64"),
+    enumerant!(12, "Int64Atomics"),
+    enumerant!(13, "ImageBasic"),
+    enumerant!(14, "ImageReadWrite"),
+    enumerant!(15, "ImageMipmap"),
+    enumerant!(17, "Pipes"),
+    enumerant!(18, "Groups"),
+    enumerant!(19, "DeviceEnqueue"),
+    enumerant!(20, "LiteralSampler"),
+    enumerant!(21, "AtomicStorage"),
+    enumerant!(22, "Int16"),
+    enumerant!(23, "TessellationPointSize"),
+    enumerant!(24, "GeometryPointSize"),
+    enumerant!(25, "ImageGatherExtended"),
+    enumerant!(27, "StorageImageMultisample"),
+    enumerant!(28, "UniformBufferArrayDynamicIndexing"),
+    enumerant!(29, "SampledImageArrayDynamicIndexing"),
+    enumerant!(30, "StorageBufferArrayDynamicIndexing"),
+    enumerant!(31, "StorageImageArrayDynamicIndexing"),
+    enumerant!(32, "ClipDistance"),
+    enumerant!(33, "CullDistance"),
+    enumerant!(34, "ImageCubeArray"),
+    enumerant!(35, "SampleRateShading"),
+    enumerant!(36, "ImageRect"),
+    enumerant!(37, "SampledRect"),
+    enumerant!(38, "GenericPointer"),
+    enumerant!(39, "Int8"),
+    enumerant!(40, "InputAttachment"),
+    enumerant!(41, "SparseResidency"),
+    enumerant!(42, "MinLod"),
+    enumerant!(43, "Sampled1D"),
+    enumerant!(44, "Image1D"),
+    enumerant!(45, "SampledCubeArray"),
+    enumerant!(46, "SampledBuffer"),
+    enumerant!(47, "ImageBuffer"),
+    enumerant!(48, "ImageMSArray"),
+    enumerant!(49, "StorageImageExtendedFormats"),
+    enumerant!(50, "ImageQuery"),
+    enumerant!(51, "DerivativeControl"),
+    enumerant!(52, "InterpolationFunction"),
+    enumerant!(53, "TransformFeedback"),
+    enumerant!(54, "GeometryStreams"),
+    enumerant!(55, "StorageImageReadWithoutFormat"),
+    enumerant!(56, "StorageImageWriteWithoutFormat"),
+    enumerant!(57, "MultiViewport"),
+];
+
+impl EnumKind {
+    pub fn enumerants(&self) -> &'static [Enumerant] {
+        match *self {
+            EnumKind::SourceLanguage => SOURCE_LANGUAGE,
+            EnumKind::ExecutionModel => EXECUTION_MODEL,
+            EnumKind::AddressingModel => ADDRESSING_MODEL,
+            EnumKind::MemoryModel => MEMORY_MODEL,
+            EnumKind::ExecutionMode => EXECUTION_MODE,
+            EnumKind::StorageClass => STORAGE_CLASS,
+            EnumKind::Dim => DIM,
+            EnumKind::ImageFormat => IMAGE_FORMAT,
+            EnumKind::AccessQualifier => ACCESS_QUALIFIER,
+            EnumKind::FunctionControl => FUNCTION_CONTROL,
+            EnumKind::MemoryAccess => MEMORY_ACCESS,
+            EnumKind::SelectionControl => SELECTION_CONTROL,
+            EnumKind::LoopControl => LOOP_CONTROL,
+            EnumKind::ImageOperands => IMAGE_OPERANDS,
+            EnumKind::Decoration => DECORATION,
+            EnumKind::BuiltIn => BUILT_IN,
+            EnumKind::FunctionParameterAttribute => FUNCTION_PARAMETER_ATTRIBUTE,
+            EnumKind::FPRoundingMode => FP_ROUNDING_MODE,
+            EnumKind::FPFastMathMode => FP_FAST_MATH_MODE,
+            EnumKind::LinkageType => LINKAGE_TYPE,
+            EnumKind::Capability => CAPABILITY,
+        }
+    }
+
+    pub fn is_bitmask(&self) -> bool {
+        match *self {
+            EnumKind::FunctionControl |
+            EnumKind::MemoryAccess |
+            EnumKind::SelectionControl |
+            EnumKind::LoopControl |
+            EnumKind::ImageOperands |
+            EnumKind::FPFastMathMode => true,
+            _ => false,
+        }
+    }
+
+    pub fn enumerant(&self, value: u32) -> Option<&'static Enumerant> {
+        self.enumerants().iter().find(|e| e.value == value)
+    }
+}
+
+pub const GLSL_STD_450: &'static str = "GLSL.std.450";
+
+const GLSL_INSTRUCTIONS: &'static [&'static str] = &[
+    "Bad", "Round", "RoundEven", "Trunc", "FAbs", "SAbs", "FSign", "SSign",
+    "Floor", "Ceil", "Fract", "Radians", "Degrees", "Sin", "Cos", "Tan",
+    "Asin", "Acos", "Atan", "Sinh", "Cosh", "Tanh", "Asinh", "Acosh",
+    "Atanh", "Atan2", "Pow", "Exp", "Log", "Exp2", "Log2", "Sqrt",
+    "InverseSqrt", "Determinant", "MatrixInverse", "Modf", "ModfStruct", "FMin", "UMin", "SMin",
+    "FMax", "UMax", "SMax", "FClamp", "UClamp", "SClamp", "FMix", "IMix",
+    "Step", "SmoothStep", "Fma", "Frexp", "FrexpStruct", "Ldexp", "PackSnorm4x8", "PackUnorm4x8",
+    "PackSnorm2x16", "PackUnorm2x16", "PackHalf2x16", "PackDouble2x32", "UnpackSnorm2x16", "UnpackUnorm2x16", "UnpackHalf2x16", "UnpackSnorm4x8",
+    "UnpackUnorm4x8", "UnpackDouble2x32", "Length", "Distance", "Cross", "Normalize", "FaceForward", "Reflect",
+    "Refract", "FindILsb", "FindSMsb", "FindUMsb", "InterpolateAtCentroid", "InterpolateAtSample", "InterpolateAtOffset", "NMin",
+    "NMax", "NClamp",
+];
+
+pub fn glsl_instruction(opcode: u32) -> Option<&'static str> {
+    if opcode == 0 {
+        return None;
+    }
+
+    GLSL_INSTRUCTIONS.get(opcode as usize).cloned()
+}
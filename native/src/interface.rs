@@ -0,0 +1,184 @@
+use std::collections::{HashMap, HashSet};
+
+use debug::DEBUG_SOURCE;
+use embed::*;
+use printer::*;
+
+const OP_DECORATE: u32 = 71;
+const DECORATION_FLAT: u32 = 14;
+
+fn operand_id(inst: &Instruction, index: usize) -> Option<u32> {
+    match inst.operands.get(index) {
+        Some(&Operand::Type(id)) | Some(&Operand::Id(id)) => Some(id),
+        _ => None,
+    }
+}
+
+// Find the Input variables holding integers or doubles, or vectors and
+// matrices of them, which fragment shaders can only read without
+// interpolation
+fn flat_inputs(listing: &Listing) -> Vec<u32> {
+    let mut flat_types = HashSet::new();
+    let mut pointers = HashMap::new();
+    let mut decorated = HashSet::new();
+    let mut inputs = Vec::new();
+
+    for inst in &listing.instructions {
+        let id = match (inst.class.as_str(), inst.operands.get(0), inst.operands.get(1)) {
+            ("OpDecorate", Some(&Operand::Id(target)), Some(&Operand::Enum(ref decoration))) => {
+                if decoration == "Flat" {
+                    decorated.insert(target);
+                }
+                continue;
+            },
+            _ => match inst.result_id {
+                Some(id) => id,
+                None => continue,
+            },
+        };
+
+        match (inst.class.as_str(), inst.operands.get(0), inst.operands.get(1)) {
+            ("OpTypeInt", _, _) |
+            ("OpTypeFloat", Some(&Operand::LitInt(64)), _) => {
+                flat_types.insert(id);
+            },
+            ("OpTypeVector", _, _) |
+            ("OpTypeMatrix", _, _) => if operand_id(inst, 0).map_or(false, |component| flat_types.contains(&component)) {
+                flat_types.insert(id);
+            },
+            ("OpTypePointer", Some(&Operand::Enum(ref storage)), _) if storage == "Input" => {
+                if let Some(pointee) = operand_id(inst, 1) {
+                    pointers.insert(id, pointee);
+                }
+            },
+            ("OpVariable", _, Some(&Operand::Enum(ref storage))) if storage == "Input" => {
+                let pointee = operand_id(inst, 0).and_then(|pointer| pointers.get(&pointer));
+                if pointee.map_or(false, |pointee| flat_types.contains(pointee)) {
+                    inputs.push(id);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    inputs.retain(|id| !decorated.contains(id));
+    inputs
+}
+
+/// Decorate the integer and double inputs of a fragment shader as Flat,
+/// since rasen leaves every input interpolated
+pub fn decorate_flat_inputs(words: &[u32]) -> Result<Vec<u32>, String> {
+    let inputs = flat_inputs(&decode_words(words)?);
+    if inputs.is_empty() {
+        return Ok(words.to_vec());
+    }
+
+    // Annotations directly follow the debug section
+    let mut preamble = PREAMBLE.to_vec();
+    preamble.extend_from_slice(DEBUG_SOURCE);
+    let offset = section_offset(words, &preamble)?;
+
+    let mut decorations = Vec::new();
+    for id in inputs {
+        push_instruction(&mut decorations, OP_DECORATE, &[id, DECORATION_FLAT]);
+    }
+
+    let mut res = Vec::with_capacity(words.len() + decorations.len());
+    res.extend_from_slice(&words[..offset]);
+    res.extend(decorations);
+    res.extend_from_slice(&words[offset..]);
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use embed::*;
+    use printer::*;
+
+    use super::decorate_flat_inputs;
+
+    // A fragment module with an int input at location 0, a float input at
+    // location 1 and an ivec2 input at location 2
+    fn module() -> Vec<u32> {
+        let mut words = vec![MAGIC_NUMBER, 0x00010000, 0, 12, 0];
+        push_instruction(&mut words, 17, &[1]);
+        push_instruction(&mut words, 14, &[0, 1]);
+
+        let mut entry = vec![4, 11];
+        entry.extend(encode_string("main"));
+        entry.extend_from_slice(&[3, 7, 10]);
+        push_instruction(&mut words, 15, &entry);
+        push_instruction(&mut words, 16, &[11, 7]);
+
+        push_instruction(&mut words, 71, &[3, 30, 0]);
+        push_instruction(&mut words, 71, &[7, 30, 1]);
+        push_instruction(&mut words, 71, &[10, 30, 2]);
+
+        push_instruction(&mut words, 21, &[1, 32, 1]);
+        push_instruction(&mut words, 32, &[2, 1, 1]);
+        push_instruction(&mut words, 59, &[2, 3, 1]);
+        push_instruction(&mut words, 22, &[5, 32]);
+        push_instruction(&mut words, 32, &[6, 1, 5]);
+        push_instruction(&mut words, 59, &[6, 7, 1]);
+        push_instruction(&mut words, 23, &[8, 1, 2]);
+        push_instruction(&mut words, 32, &[9, 1, 8]);
+        push_instruction(&mut words, 59, &[9, 10, 1]);
+
+        words
+    }
+
+    fn flat_targets(instructions: &[Instruction]) -> Vec<u32> {
+        instructions.iter()
+            .filter_map(|inst| match (inst.class.as_str(), inst.operands.get(0), inst.operands.get(1)) {
+                ("OpDecorate", Some(&Operand::Id(target)), Some(&Operand::Enum(ref decoration))) if decoration == "Flat" => Some(target),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn integer_inputs_are_flat() {
+        let words = decorate_flat_inputs(&module()).unwrap();
+        let listing = decode_words(&words).unwrap();
+
+        assert_eq!(flat_targets(&listing.instructions), vec![3, 10]);
+
+        let first_decoration = listing.instructions.iter()
+            .position(|inst| inst.class == "OpDecorate")
+            .unwrap();
+        assert_eq!(flat_targets(&listing.instructions[first_decoration..first_decoration + 1]), vec![3]);
+    }
+
+    #[test]
+    fn double_matrix_inputs_are_flat() {
+        // dmat2 input at location 0, mat2 input at location 1
+        let mut words = vec![MAGIC_NUMBER, 0x00010000, 0, 12, 0];
+        push_instruction(&mut words, 17, &[1]);
+        push_instruction(&mut words, 14, &[0, 1]);
+
+        push_instruction(&mut words, 22, &[1, 64]);
+        push_instruction(&mut words, 23, &[2, 1, 2]);
+        push_instruction(&mut words, 24, &[3, 2, 2]);
+        push_instruction(&mut words, 32, &[4, 1, 3]);
+        push_instruction(&mut words, 59, &[4, 5, 1]);
+        push_instruction(&mut words, 22, &[6, 32]);
+        push_instruction(&mut words, 23, &[7, 6, 2]);
+        push_instruction(&mut words, 24, &[8, 7, 2]);
+        push_instruction(&mut words, 32, &[9, 1, 8]);
+        push_instruction(&mut words, 59, &[9, 10, 1]);
+
+        let words = decorate_flat_inputs(&words).unwrap();
+        let listing = decode_words(&words).unwrap();
+
+        assert_eq!(flat_targets(&listing.instructions), vec![5]);
+    }
+
+    #[test]
+    fn decorations_are_not_repeated() {
+        let once = decorate_flat_inputs(&module()).unwrap();
+        let twice = decorate_flat_inputs(&once).unwrap();
+
+        assert_eq!(once, twice);
+    }
+}
@@ -1,101 +1,80 @@
-#![feature(specialization)]
+//! Compiler for the node graphs of rasen-editor
+//!
+//! Graph documents are JSON objects holding a map of `nodes` and a list of
+//! `edges`. They can be checked with `parse_graph` and compiled to SPIR-V
+//! with `compile`. The editor uses the same functions through the C
+//! interface in the `ffi` module.
+
+#![deny(missing_docs)]
 
 extern crate error_chain;
-extern crate spirv_utils;
 extern crate serde;
+#[macro_use]
+extern crate serde_derive;
 extern crate serde_json;
 extern crate rasen;
 
+mod compiler;
+mod debug;
+mod decompiler;
+mod diagnostic;
+mod embed;
+mod grammar;
+mod interface;
+mod migrate;
+mod names;
 mod parser;
 mod printer;
+mod schema;
+mod typeck;
+mod validate;
 
-use error_chain::ChainedError;
-use std::ffi::{CStr, CString};
-use std::os::raw::{
-    c_char, c_void,
-};
-use std::mem;
-use std::fmt::Write;
-
-use rasen::*;
-
-use parser::*;
-use printer::*;
+pub mod ffi;
 
-fn print_err<E>(e: E) -> String where E: ChainedError {
-    let mut err = String::new();
-    writeln!(&mut err, "{}", e).unwrap();
+use std::collections::BTreeMap;
 
-    for e in e.iter().skip(1) {
-        writeln!(&mut err, "caused by: {}", e).unwrap();
-    }
+use serde_json::{to_string, Value};
 
-    if let Some(backtrace) = e.backtrace() {
-        writeln!(&mut err, "backtrace: {:?}", backtrace).unwrap();
-    }
+pub use compiler::{Program, EMBED_GRAPH, DEBUG_NAMES, FRIENDLY_NAMES};
+pub use diagnostic::{Diagnostic, Severity};
+pub use migrate::{Upgrade, CURRENT_VERSION};
+pub use parser::{Edge, ParsedGraph};
+pub use printer::{Header, Instruction, Listing, Operand};
+pub use schema::{read_document, EdgeDocument, GraphDocument, NodeData, NodeDocument, Stage};
+pub use typeck::{Kind, NodeTypes, Scalar, Type};
 
-    err
+/// Parse a graph document into a rasen graph, after checking its structure
+/// and the types flowing along its edges
+pub fn parse_graph(input: &str) -> Result<ParsedGraph, Vec<Diagnostic>> {
+    parser::parse_input(String::from(input))
 }
 
-fn convert_asm(input: String) -> Result<String, String> {
-    let graph = parse_input(input)?;
-    module_printer(
-        Module::build(&graph, ShaderType::Fragment)
-            .map_err(print_err)?
-    )
+/// Build a graph from a document that was already read, or created in code
+pub fn parse_document(document: &GraphDocument) -> Result<ParsedGraph, Vec<Diagnostic>> {
+    parser::parse_document(document)
 }
 
-fn convert_bc(input: String) -> Result<Vec<u8>, String> {
-    let graph = parse_input(input)?;
-    build_program(&graph, ShaderType::Fragment)
-        .map_err(print_err)
+/// Infer the types of a graph that may not build yet, returning the types
+/// that could be deduced along with the diagnostics of the whole graph
+pub fn infer_types(input: &str) -> Result<(BTreeMap<String, NodeTypes>, Vec<Diagnostic>), Vec<Diagnostic>> {
+    parser::infer_input(input)
 }
 
-#[no_mangle]
-pub extern fn to_assembly(input: *const c_char) -> *mut c_char {
-    let input = unsafe {
-        CStr::from_ptr(input).to_string_lossy().into_owned()
-    };
-
-    let result = match convert_asm(input) {
-        Ok(code) => code,
-        Err(msg) => format!("{{\"error\":{:?}}}", msg)
-    };
-
-    let c_string = CString::new(result).unwrap();
-    let ret: *mut c_char = unsafe {
-        mem::transmute(c_string.as_ptr())
-    };
-    mem::forget(c_string);
-    ret
+/// Upgrade a graph document written by an older version of the editor to
+/// `CURRENT_VERSION`, along with a description of what changed
+pub fn upgrade_graph(input: &str) -> Result<(Value, Upgrade), Vec<Diagnostic>> {
+    migrate::upgrade_input(input)
 }
 
-#[repr(C)]
-pub struct Array {
-    len: usize,
-    data: *const c_void,
+/// Compile a graph document to a SPIR-V module, `flags` is a combination of
+/// `EMBED_GRAPH`, `DEBUG_NAMES` and `FRIENDLY_NAMES`
+pub fn compile(input: &str, flags: u32) -> Result<Program, Vec<Diagnostic>> {
+    compiler::build(String::from(input), flags)
 }
 
-#[no_mangle]
-pub extern fn to_bytecode(input: *const c_char) -> *const u8 {
-    let input = unsafe {
-        CStr::from_ptr(input).to_string_lossy().into_owned()
-    };
-
-    let vec = match convert_bc(input) {
-        Ok(code) => code,
-        Err(msg) => msg.into_bytes()
-    };
-
-    let size: [u8; 8] = unsafe {
-        mem::transmute(vec.len() as u64)
-    };
-
-    let mut res = Vec::new();
-    res.extend(size.into_iter());
-    res.extend(vec.into_iter());
-
-    let ptr = res.as_ptr();
-    mem::forget(res);
-    ptr
+/// Compile a graph document and print its module as the JSON listing
+/// displayed by the editor
+pub fn print_module(input: &str, flags: u32) -> Result<String, Vec<Diagnostic>> {
+    let listing = compile(input, flags)?.listing(flags)?;
+    to_string(&listing.to_value()).map_err(|err| compiler::print_failed(format!("{}", err)))
 }
@@ -0,0 +1,132 @@
+use serde_json::{from_str, Value, Map};
+
+use diagnostic::*;
+
+/// Version of the graph documents written by this version of the editor
+pub const CURRENT_VERSION: u64 = 1;
+
+// A step upgrading documents from one version to the next, returning a
+// description of each change it made
+struct Migration {
+    from: u64,
+    apply: fn(&mut Map<String, Value>) -> Vec<String>,
+}
+
+// One migration per version, in order
+const MIGRATIONS: &'static [Migration] = &[
+    Migration {
+        from: 0,
+        apply: explicit_stage,
+    },
+];
+
+// Unversioned documents were compiled as fragment shaders when they did not
+// specify a stage, which is still the default. This only normalises the
+// document so the stage is always written out and shown in the editor.
+fn explicit_stage(document: &mut Map<String, Value>) -> Vec<String> {
+    if document.contains_key("stage") {
+        return Vec::new();
+    }
+
+    document.insert(String::from("stage"), Value::String(String::from("fragment")));
+    vec![String::from("shader stage set to fragment")]
+}
+
+/// Summary of the migrations applied to a document
+#[derive(Debug, Clone)]
+pub struct Upgrade {
+    /// Version the document was written with, 0 when it had none
+    pub from: u64,
+    /// Version the document was brought to
+    pub to: u64,
+    /// Description of each change made to the document
+    pub changes: Vec<String>,
+}
+
+fn document_version(document: &Map<String, Value>) -> Result<u64, String> {
+    match document.get("version") {
+        Some(version) => version.as_u64().ok_or(String::from("data version is not an unsigned integer")),
+        None => Ok(0),
+    }
+}
+
+/// Bring a graph document up to `CURRENT_VERSION`, documents without a
+/// version field are treated as version 0
+pub fn upgrade(document: &mut Map<String, Value>) -> Result<Upgrade, String> {
+    let from = document_version(document)?;
+    if from > CURRENT_VERSION {
+        return Err(format!("Graph version {} is newer than the supported version {}", from, CURRENT_VERSION));
+    }
+
+    let mut changes = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|migration| migration.from >= from) {
+        changes.extend((migration.apply)(document));
+    }
+
+    document.insert(String::from("version"), Value::U64(CURRENT_VERSION));
+
+    Ok(Upgrade {
+        from: from,
+        to: CURRENT_VERSION,
+        changes: changes,
+    })
+}
+
+/// Parse and upgrade a graph document, returning the upgraded document
+pub fn upgrade_input(input: &str) -> Result<(Value, Upgrade), Vec<Diagnostic>> {
+    let mut document = match from_str(input) {
+        Ok(Value::Object(document)) => document,
+        Ok(_) => return Err(vec![Diagnostic::error("JSON input is not an object")]),
+        Err(err) => return Err(vec![
+            Diagnostic::error(format!("JSON error: {:?}", err))
+        ]),
+    };
+
+    let summary = upgrade(&mut document).map_err(|msg| vec![Diagnostic::from(msg)])?;
+    Ok((Value::Object(document), summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, Value, Map};
+
+    use super::upgrade;
+
+    fn document(input: &str) -> Map<String, Value> {
+        from_str(input).unwrap()
+    }
+
+    #[test]
+    fn unversioned_documents_get_a_stage() {
+        let mut data = document(r#"{ "nodes": {}, "edges": [] }"#);
+        let summary = upgrade(&mut data).unwrap();
+
+        assert_eq!((summary.from, summary.to), (0, 1));
+        assert_eq!(summary.changes, vec![String::from("shader stage set to fragment")]);
+        assert_eq!(data["version"], Value::U64(1));
+        assert_eq!(data["stage"], Value::String(String::from("fragment")));
+    }
+
+    #[test]
+    fn current_documents_are_unchanged() {
+        let input = r#"{ "version": 1, "stage": "vertex", "nodes": {}, "edges": [] }"#;
+        let mut data = document(input);
+        let summary = upgrade(&mut data).unwrap();
+
+        assert_eq!((summary.from, summary.to), (1, 1));
+        assert!(summary.changes.is_empty());
+        assert_eq!(data, document(input));
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        let mut data = document(r#"{ "version": 2, "nodes": {}, "edges": [] }"#);
+        assert_eq!(upgrade(&mut data).unwrap_err(), "Graph version 2 is newer than the supported version 1");
+
+        let mut data = document(r#"{ "version": "1", "nodes": {}, "edges": [] }"#);
+        assert_eq!(upgrade(&mut data).unwrap_err(), "data version is not an unsigned integer");
+
+        let mut data = document(r#"{ "version": 1.5, "nodes": {}, "edges": [] }"#);
+        assert_eq!(upgrade(&mut data).unwrap_err(), "data version is not an unsigned integer");
+    }
+}
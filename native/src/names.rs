@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use decompiler::{node_title, glsl_title};
+use grammar::is_type_declaration;
+use printer::*;
+
+fn literal_name(operand: &Operand) -> Option<String> {
+    let text = match *operand {
+        Operand::LitInt(val) => format!("{}", val),
+        Operand::LitSInt(val) => format!("{}", val),
+        Operand::LitLong(val) => format!("{}", val),
+        Operand::LitSLong(val) => format!("{}", val),
+        Operand::LitFloat(val) if val.is_finite() => format!("{}", val),
+        Operand::LitDouble(val) if val.is_finite() => format!("{}", val),
+        _ => return None,
+    };
+
+    if text.contains('e') {
+        return None;
+    }
+
+    Some(text.replace('-', "n").replace('.', "_"))
+}
+
+fn type_name(inst: &Instruction, types: &HashMap<u32, String>) -> Option<String> {
+    let operand_type = |index: usize| match inst.operands.get(index) {
+        Some(&Operand::Type(id)) | Some(&Operand::Id(id)) => types.get(&id),
+        _ => None,
+    };
+    let operand_int = |index: usize| match inst.operands.get(index) {
+        Some(&Operand::LitInt(val)) => Some(val),
+        _ => None,
+    };
+
+    match (inst.class.as_str(), inst.operands.get(0)) {
+        ("OpTypeVoid", _) => Some(String::from("void")),
+        ("OpTypeBool", _) => Some(String::from("bool")),
+        ("OpTypeInt", _) => match (operand_int(0), operand_int(1)) {
+            (Some(32), Some(0)) => Some(String::from("uint")),
+            (Some(32), Some(_)) => Some(String::from("int")),
+            (Some(64), Some(0)) => Some(String::from("ulong")),
+            (Some(64), Some(_)) => Some(String::from("long")),
+            _ => None,
+        },
+        ("OpTypeFloat", _) => match operand_int(0) {
+            Some(32) => Some(String::from("float")),
+            Some(64) => Some(String::from("double")),
+            _ => None,
+        },
+        ("OpTypeVector", _) => match (operand_int(1), operand_type(0)) {
+            (Some(count), Some(component)) => Some(format!("v{}{}", count, component)),
+            _ => None,
+        },
+        ("OpTypeMatrix", _) => match (operand_int(1), operand_type(0)) {
+            (Some(count), Some(column)) => Some(format!("mat{}{}", count, column)),
+            _ => None,
+        },
+        ("OpTypePointer", Some(&Operand::Enum(ref storage))) => {
+            operand_type(1).map(|pointee| format!("ptr_{}_{}", storage, pointee))
+        },
+        ("OpTypeFunction", _) => {
+            operand_type(0).map(|result| format!("fn_{}", result))
+        },
+        _ => None,
+    }
+}
+
+fn value_name(inst: &Instruction, id: u32) -> Option<String> {
+    let title = match inst.class.as_str() {
+        "OpCompositeConstruct" => Some("construct"),
+        "OpCompositeExtract" => Some("extract"),
+        "OpLoad" => Some("load"),
+        "OpExtInst" => match inst.operands.get(2) {
+            Some(&Operand::ExtInst(ref name)) => glsl_title(name),
+            _ => None,
+        },
+        _ => node_title(inst),
+    };
+
+    title.map(|title| format!("{}_{}", title.to_lowercase(), id))
+}
+
+/// Derive readable names for the result ids of a module from its types,
+/// interface variables and the graph nodes its instructions stand for. Ids
+/// whose name would be shared with another id keep their number.
+pub fn friendly_names(listing: &Listing) -> HashMap<u32, String> {
+    let mut types = HashMap::new();
+    let mut locations = HashMap::new();
+    let mut candidates = Vec::new();
+
+    for inst in &listing.instructions {
+        match (inst.class.as_str(), inst.operands.get(0), inst.operands.get(1), inst.operands.get(2)) {
+            ("OpDecorate", Some(&Operand::Id(target)), Some(&Operand::Enum(ref decoration)), Some(&Operand::LitInt(location))) => {
+                if decoration == "Location" {
+                    locations.insert(target, location);
+                }
+            },
+            ("OpEntryPoint", _, Some(&Operand::Id(function)), Some(&Operand::LitString(ref name))) => {
+                candidates.push((function, name.clone()));
+            },
+            _ => {},
+        }
+
+        let id = match inst.result_id {
+            Some(id) => id,
+            None => continue,
+        };
+
+        let name = match inst.class.as_str() {
+            _ if is_type_declaration(inst.opcode) => {
+                let name = type_name(inst, &types);
+                if let Some(ref name) = name {
+                    types.insert(id, name.clone());
+                }
+                name
+            },
+
+            "OpConstantTrue" => Some(String::from("true")),
+            "OpConstantFalse" => Some(String::from("false")),
+            "OpConstant" => match (inst.operands.get(0), inst.operands.get(1)) {
+                (Some(&Operand::Type(ty)), Some(value)) => match (types.get(&ty), literal_name(value)) {
+                    (Some(ty), Some(value)) => Some(format!("{}_{}", ty, value)),
+                    _ => None,
+                },
+                _ => None,
+            },
+
+            "OpVariable" => match (inst.operands.get(1), locations.get(&id)) {
+                (Some(&Operand::Enum(ref storage)), Some(location)) if storage == "Input" => Some(format!("in_loc{}", location)),
+                (Some(&Operand::Enum(ref storage)), Some(location)) if storage == "Output" => Some(format!("out_loc{}", location)),
+                _ => None,
+            },
+
+            _ => value_name(inst, id),
+        };
+
+        if let Some(name) = name {
+            candidates.push((id, name));
+        }
+    }
+
+    let mut counts = HashMap::new();
+    for &(_, ref name) in &candidates {
+        *counts.entry(name.clone()).or_insert(0) += 1;
+    }
+
+    candidates.into_iter()
+        .filter(|&(_, ref name)| counts[name] == 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use embed::*;
+    use printer::*;
+
+    use super::friendly_names;
+
+    #[test]
+    fn ids_are_named_after_what_they_hold() {
+        let mut words = vec![MAGIC_NUMBER, 0x00010000, 0, 20, 0];
+        push_instruction(&mut words, 17, &[1]);
+
+        let mut import = vec![1];
+        import.extend(encode_string("GLSL.std.450"));
+        push_instruction(&mut words, 11, &import);
+        push_instruction(&mut words, 14, &[0, 1]);
+
+        let mut entry = vec![4, 10];
+        entry.extend(encode_string("main"));
+        entry.extend_from_slice(&[7, 9]);
+        push_instruction(&mut words, 15, &entry);
+        push_instruction(&mut words, 16, &[10, 7]);
+
+        push_instruction(&mut words, 71, &[7, 30, 0]);
+        push_instruction(&mut words, 71, &[9, 30, 0]);
+
+        push_instruction(&mut words, 19, &[2]);
+        push_instruction(&mut words, 33, &[3, 2]);
+        push_instruction(&mut words, 22, &[4, 32]);
+        push_instruction(&mut words, 23, &[5, 4, 3]);
+        push_instruction(&mut words, 32, &[6, 1, 5]);
+        push_instruction(&mut words, 59, &[6, 7, 1]);
+        push_instruction(&mut words, 32, &[8, 3, 5]);
+        push_instruction(&mut words, 59, &[8, 9, 3]);
+
+        // %15 and %16 would share a name
+        push_instruction(&mut words, 43, &[4, 14, 0x40000000]);
+        push_instruction(&mut words, 43, &[4, 15, 0x3f800000]);
+        push_instruction(&mut words, 43, &[4, 16, 0x3f800000]);
+
+        push_instruction(&mut words, 54, &[2, 10, 0, 3]);
+        push_instruction(&mut words, 248, &[11]);
+        push_instruction(&mut words, 61, &[5, 12, 7]);
+        push_instruction(&mut words, 12, &[5, 13, 1, 69, 12]);
+        push_instruction(&mut words, 62, &[9, 13]);
+        push_instruction(&mut words, 253, &[]);
+        push_instruction(&mut words, 56, &[]);
+
+        let names = friendly_names(&decode_words(&words).unwrap());
+        let name = |id: u32| names.get(&id).map(String::as_str);
+
+        assert_eq!(name(2), Some("void"));
+        assert_eq!(name(3), Some("fn_void"));
+        assert_eq!(name(4), Some("float"));
+        assert_eq!(name(5), Some("v3float"));
+        assert_eq!(name(6), Some("ptr_Input_v3float"));
+        assert_eq!(name(7), Some("in_loc0"));
+        assert_eq!(name(9), Some("out_loc0"));
+        assert_eq!(name(10), Some("main"));
+        assert_eq!(name(12), Some("load_12"));
+        assert_eq!(name(13), Some("normalize_13"));
+        assert_eq!(name(14), Some("float_2"));
+
+        assert_eq!(name(15), None);
+        assert_eq!(name(16), None);
+        assert_eq!(name(11), None);
+    }
+}
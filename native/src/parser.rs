@@ -1,16 +1,17 @@
-use serde_json::{from_str, Value, Map};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use rasen::*;
 
+use diagnostic::*;
+use schema::*;
+use typeck::*;
+use validate::*;
+
 fn as_typename(name: &str, node: &'static str) -> Result<&'static TypeName, String> {
     TypeName::from_string(name).ok_or(format!("Unknown {} type {:?}", node, name))
 }
 
-fn get_prop<'a>(obj: &'a Map<String, Value>, key: &str, context: &'static str) -> Result<&'a Value, String> {
-    obj.get(key).ok_or(format!("{}.{} is undefined", context, key))
-}
-
 fn as_float(value: &Value, context: &'static str) -> Result<f32, String> {
     Ok(value.as_f64().ok_or(format!("{} is not a float", context))? as f32)
 }
@@ -25,240 +26,769 @@ fn as_uint(value: &Value, context: &'static str) -> Result<u32, String> {
     Ok(value.as_u64().ok_or(format!("{} is not an unsigned integer", context))? as u32)
 }
 
-fn as_str<'a>(value: &'a Value, context: &'static str) -> Result<&'a str, String> {
-    value.as_str().ok_or(format!("{} is not a string", context))
+// Flatten a matrix given as an array of columns into column-major order
+fn as_matrix<T, F>(value: &Value, size: u32, element: F) -> Result<Vec<T>, String> where F: Fn(&Value) -> Result<T, String> {
+    let columns = value.as_array().ok_or("constant value is not an array")?;
+    if columns.len() != size as usize {
+        return Err(format!("Wrong column count for mat{}: {}", size, columns.len()));
+    }
+
+    let mut values = Vec::with_capacity((size * size) as usize);
+    for column in columns {
+        let column = column.as_array().ok_or("matrix column is not an array")?;
+        if column.len() != size as usize {
+            return Err(format!("Wrong column length for mat{}: {}", size, column.len()));
+        }
+
+        for value in column {
+            values.push(element(value)?);
+        }
+    }
+
+    Ok(values)
+}
+
+// Reject interface variables whose type cannot cross the given stage boundary
+fn check_stage(stage: &ShaderType, node: &Node) -> Result<(), String> {
+    match (stage, node) {
+        (&ShaderType::Vertex, &Node::Input(location, data_type)) => match *data_type {
+            TypeName::Bool | TypeName::Vec(_, &TypeName::Bool) =>
+                Err(format!("Vertex input {} cannot be a boolean", location)),
+            _ => Ok(()),
+        },
+        (&ShaderType::Vertex, &Node::Output(location, data_type)) => match *data_type {
+            TypeName::Bool | TypeName::Vec(_, &TypeName::Bool) =>
+                Err(format!("Vertex output {} cannot be a boolean", location)),
+            _ => Ok(()),
+        },
+
+        (&ShaderType::Fragment, &Node::Input(location, data_type)) => match *data_type {
+            TypeName::Bool | TypeName::Vec(_, &TypeName::Bool) =>
+                Err(format!("Fragment input {} cannot be a boolean", location)),
+            _ => Ok(()),
+        },
+        (&ShaderType::Fragment, &Node::Output(location, data_type)) => match *data_type {
+            TypeName::Bool | TypeName::Vec(_, &TypeName::Bool) =>
+                Err(format!("Fragment output {} cannot be a boolean", location)),
+            TypeName::Float(true) | TypeName::Vec(_, &TypeName::Float(true)) =>
+                Err(format!("Fragment output {} cannot be a double", location)),
+            TypeName::Mat(_, _) =>
+                Err(format!("Fragment output {} cannot be a matrix", location)),
+            _ => Ok(()),
+        },
+
+        _ => Ok(()),
+    }
+}
+
+fn parse_node(data: &NodeData) -> Result<Node, String> {
+    Ok(match *data {
+        NodeData::Input { location, ref data_type } => {
+            Node::Input(location, as_typename(data_type, "input")?)
+        },
+        NodeData::Output { location, ref data_type } => {
+            Node::Output(location, as_typename(data_type, "output")?)
+        },
+
+        NodeData::Construct { ref data_type } => {
+            Node::Construct(as_typename(data_type, "construct")?)
+        },
+        NodeData::Extract { index } => {
+            Node::Extract(index)
+        },
+
+        NodeData::Constant { ref data_type, ref value } => {
+            Node::Constant(parse_constant(data_type, value)?)
+        },
+
+        NodeData::Operation(ref title) => Node::from_string(title).ok_or(format!("Unimplemented node {:?}", title))?,
+    })
+}
+
+fn parse_constant(data_type: &str, value: &Value) -> Result<TypedValue, String> {
+    Ok(match as_typename(data_type, "constant")? {
+        &TypeName::Bool => {
+            let value = value.as_bool().ok_or("constant value is not a boolean")?;
+            TypedValue::Bool(value)
+        },
+
+        &TypeName::Int(signed) => if signed {
+            TypedValue::Int(
+                as_int(&value, "constant value")?
+            )
+        } else {
+            TypedValue::UInt(
+                as_uint(&value, "constant value")?
+            )
+        },
+
+        &TypeName::Float(precision) => if precision {
+            TypedValue::Double(
+                as_double(&value, "constant value")?
+            )
+        } else {
+            TypedValue::Float(
+                as_float(&value, "constant value")?
+            )
+        },
+
+        &TypeName::Vec(size, scalar) => {
+            let value = value.as_array().ok_or("constant value is not an array")?;
+            if value.len() != size as usize {
+                return Err(format!("Wrong array length for {}", data_type));
+            }
+
+            match scalar {
+                &TypeName::Bool => {
+                    let res: Result<Vec<_>, _> =
+                        value.into_iter()
+                            .map(|v| v.as_bool().ok_or("vector element is not a boolean"))
+                            .collect();
+
+                    let values = res?;
+                    match size {
+                        2 => TypedValue::BVec2(
+                            values[0], values[1]
+                        ),
+                        3 => TypedValue::BVec3(
+                            values[0], values[1], values[2]
+                        ),
+                        4 => TypedValue::BVec4(
+                            values[0], values[1], values[2], values[3]
+                        ),
+                        _ => return Err(format!("Wrong bvec size: {}", size))
+                    }
+                },
+
+                &TypeName::Int(signed) => if signed {
+                    let res: Result<Vec<_>, _> =
+                        value.into_iter()
+                            .map(|v| as_int(&v, "vector element"))
+                            .collect();
+
+                    let values = res?;
+                    match size {
+                        2 => TypedValue::IVec2(
+                            values[0], values[1]
+                        ),
+                        3 => TypedValue::IVec3(
+                            values[0], values[1], values[2]
+                        ),
+                        4 => TypedValue::IVec4(
+                            values[0], values[1], values[2], values[3]
+                        ),
+                        _ => return Err(format!("Wrong ivec size: {}", size))
+                    }
+                } else {
+                    let res: Result<Vec<_>, _> =
+                        value.into_iter()
+                            .map(|v| as_uint(&v, "vector element"))
+                            .collect();
+
+                    let values = res?;
+                    match size {
+                        2 => TypedValue::UVec2(
+                            values[0], values[1]
+                        ),
+                        3 => TypedValue::UVec3(
+                            values[0], values[1], values[2]
+                        ),
+                        4 => TypedValue::UVec4(
+                            values[0], values[1], values[2], values[3]
+                        ),
+                        _ => return Err(format!("Wrong uvec size: {}", size))
+                    }
+                },
+
+                &TypeName::Float(precision) => if precision {
+                    let res: Result<Vec<_>, _> =
+                        value.into_iter()
+                            .map(|v| as_double(&v, "vector element"))
+                            .collect();
+
+                    let values = res?;
+                    match size {
+                        2 => TypedValue::DVec2(
+                            values[0], values[1]
+                        ),
+                        3 => TypedValue::DVec3(
+                            values[0], values[1], values[2]
+                        ),
+                        4 => TypedValue::DVec4(
+                            values[0], values[1], values[2], values[3]
+                        ),
+                        _ => return Err(format!("Wrong dvec size: {}", size))
+                    }
+                } else {
+                    let res: Result<Vec<_>, _> =
+                        value.into_iter()
+                            .map(|v| as_float(&v, "vector element"))
+                            .collect();
+
+                    let values = res?;
+                    match size {
+                        2 => TypedValue::Vec2(
+                            values[0], values[1]
+                        ),
+                        3 => TypedValue::Vec3(
+                            values[0], values[1], values[2]
+                        ),
+                        4 => TypedValue::Vec4(
+                            values[0], values[1], values[2], values[3]
+                        ),
+                        _ => return Err(format!("Wrong vec size: {}", size))
+                    }
+                },
+
+                _ => return Err(format!("Wrong vector scalar type: {:?}", *scalar)),
+            }
+        },
+
+        &TypeName::Mat(size, scalar) => match scalar {
+            &TypeName::Float(precision) => if precision {
+                let values = as_matrix(&value, size, |v| as_double(v, "matrix element"))?;
+                match size {
+                    2 => {
+                        let mut mat = [0f64; 4];
+                        mat.copy_from_slice(&values);
+                        TypedValue::DMat2(mat)
+                    },
+                    3 => {
+                        let mut mat = [0f64; 9];
+                        mat.copy_from_slice(&values);
+                        TypedValue::DMat3(mat)
+                    },
+                    4 => {
+                        let mut mat = [0f64; 16];
+                        mat.copy_from_slice(&values);
+                        TypedValue::DMat4(mat)
+                    },
+                    _ => return Err(format!("Wrong dmat size: {}", size))
+                }
+            } else {
+                let values = as_matrix(&value, size, |v| as_float(v, "matrix element"))?;
+                match size {
+                    2 => {
+                        let mut mat = [0f32; 4];
+                        mat.copy_from_slice(&values);
+                        TypedValue::Mat2(mat)
+                    },
+                    3 => {
+                        let mut mat = [0f32; 9];
+                        mat.copy_from_slice(&values);
+                        TypedValue::Mat3(mat)
+                    },
+                    4 => {
+                        let mut mat = [0f32; 16];
+                        mat.copy_from_slice(&values);
+                        TypedValue::Mat4(mat)
+                    },
+                    _ => return Err(format!("Wrong mat size: {}", size))
+                }
+            },
+
+            _ => return Err(format!("Wrong matrix scalar type: {:?}", *scalar)),
+        },
+
+        _ => return Err(format!("Unknown constant type {:?}", data_type))
+    })
+}
+
+/// An edge between two node keys, along with its index in the input document
+pub struct Edge {
+    /// Index of the edge in the document
+    pub index: usize,
+    /// Key of the node the value comes from
+    pub from: String,
+    /// Key of the node the value goes to
+    pub to: String,
+    /// Input of the destination node
+    pub input: u32,
 }
 
-pub fn parse_input(input: String) -> Result<Graph, String> {
-    let data: Value = match from_str(&input) {
-        Ok(val) => val,
-        Err(err) => return Err(format!("JSON error: {:?}", err))
-    };
+/// A graph that passed every check and can be built by rasen
+pub struct ParsedGraph {
+    /// The rasen graph
+    pub graph: Graph,
+    /// Stage the graph is compiled for
+    pub stage: ShaderType,
+    /// Warnings found while reading and checking the graph
+    pub warnings: Vec<Diagnostic>,
+    /// Kind of each node, by key
+    pub nodes: BTreeMap<String, Kind>,
+    /// Edges of the graph
+    pub edges: Vec<Edge>,
+    /// Types inferred for each node, by key
+    pub types: BTreeMap<String, NodeTypes>,
+}
+
+pub fn parse_input(input: String) -> Result<ParsedGraph, Vec<Diagnostic>> {
+    let (document, diagnostics) = read_document(&input)?;
+    let unread = unread_nodes(&diagnostics);
+
+    let (mut errors, mut warnings): (Vec<_>, Vec<_>) = diagnostics.into_iter()
+        .partition(|diag| diag.severity == Severity::Error);
+
+    match build_document(&document, &unread) {
+        Ok(mut parsed) => {
+            if !errors.is_empty() {
+                return Err(errors);
+            }
+
+            warnings.append(&mut parsed.warnings);
+            parsed.warnings = warnings;
+
+            Ok(parsed)
+        },
+        Err(more) => {
+            errors.extend(more);
+            Err(errors)
+        },
+    }
+}
+
+/// Read a document that may not build yet and infer its types, along with
+/// the diagnostics of both reading and checking it
+pub fn infer_input(input: &str) -> Result<(BTreeMap<String, NodeTypes>, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let (document, mut diagnostics) = read_document(input)?;
+    let unread = unread_nodes(&diagnostics);
+
+    let (types, errors) = infer_partial(&document, &unread);
+    diagnostics.extend(errors);
+
+    Ok((types, diagnostics))
+}
+
+// Keys of the nodes left out of the document because they couldn't be read
+fn unread_nodes(diagnostics: &[Diagnostic]) -> HashSet<String> {
+    diagnostics.iter()
+        .filter(|diag| diag.severity == Severity::Error)
+        .filter_map(|diag| diag.node.clone())
+        .collect()
+}
+
+// Walk the edges backwards from the outputs, collecting every node whose
+// value ends up in one of them
+fn live_nodes(sinks: &[String], links: &[Edge]) -> HashSet<String> {
+    let mut sources = HashMap::new();
+    for link in links {
+        sources.entry(link.to.as_str())
+            .or_insert_with(Vec::new)
+            .push(link.from.as_str());
+    }
+
+    let mut live = HashSet::new();
+    let mut stack: Vec<&str> = sinks.iter().map(|key| key.as_str()).collect();
+    while let Some(key) = stack.pop() {
+        if !live.insert(String::from(key)) {
+            continue;
+        }
 
-    let data = data.as_object().ok_or("JSON input is not an object")?;
+        if let Some(from) = sources.get(key) {
+            stack.extend(from.iter().cloned());
+        }
+    }
+
+    live
+}
+
+// Build the nodes of the document that are valid for its stage
+fn collect_nodes(document: &GraphDocument, stage: &ShaderType) -> (BTreeMap<String, Node>, Vec<Diagnostic>) {
+    let mut nodes = BTreeMap::new();
+    let mut errors = Vec::new();
+
+    for (key, node) in document.nodes.iter() {
+        let node = parse_node(&node.data)
+            .and_then(|node| {
+                check_stage(stage, &node)?;
+                Ok(node)
+            });
+
+        match node {
+            Ok(node) => {
+                nodes.insert(key.clone(), node);
+            },
+            Err(msg) => errors.push(Diagnostic::from(msg).at_node(key.as_str())),
+        }
+    }
+
+    (nodes, errors)
+}
+
+// Keep the edges between nodes that were built, along with the inputs of
+// built nodes fed by a node that failed to read or build
+fn collect_edges<T>(document: &GraphDocument, nodes: &BTreeMap<String, T>, unread: &HashSet<String>) -> (Vec<Edge>, HashSet<(String, u32)>, Vec<Diagnostic>) {
+    let mut links = Vec::new();
+    let mut fed = HashSet::new();
+    let mut errors = Vec::new();
+
+    for (index, edge) in document.edges.iter().enumerate() {
+        let from = format!("{}", edge.from);
+        let to = format!("{}", edge.to);
+
+        // Edges touching a node that failed to read or build are not
+        // reported again
+        if !nodes.contains_key(&from) {
+            if !document.nodes.contains_key(&from) && !unread.contains(&from) {
+                errors.push(Diagnostic::error("edge origin node is undefined").at_edge(index));
+            } else {
+                fed.insert((to, edge.input));
+            }
+            continue;
+        }
+        if !nodes.contains_key(&to) {
+            if !document.nodes.contains_key(&to) && !unread.contains(&to) {
+                errors.push(Diagnostic::error("edge destination node is undefined").at_edge(index));
+            }
+            continue;
+        }
+
+        links.push(Edge {
+            index: index,
+            from: from,
+            to: to,
+            input: edge.input,
+        });
+    }
+
+    (links, fed, errors)
+}
+
+pub fn parse_document(document: &GraphDocument) -> Result<ParsedGraph, Vec<Diagnostic>> {
+    build_document(document, &HashSet::new())
+}
+
+fn build_document(document: &GraphDocument, unread: &HashSet<String>) -> Result<ParsedGraph, Vec<Diagnostic>> {
+    let stage = document.stage.shader_type();
+
+    let (nodes, mut errors) = collect_nodes(document, &stage);
+    let (links, fed, edge_errors) = collect_edges(document, &nodes, unread);
+    errors.extend(edge_errors);
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let kinds: BTreeMap<_, _> = nodes.iter()
+        .map(|(key, node)| (key.clone(), Kind::from_node(node)))
+        .collect();
+
+    let errors = validate(&kinds, &links, &fed);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let types = check_types(&kinds, &links)?;
 
     let mut graph = Graph::new();
+    let mut sinks = Vec::new();
     let mut mappings = HashMap::new();
+    for (key, node) in nodes {
+        if let Node::Output(..) = node {
+            sinks.push(key.clone());
+        }
 
-    let nodes = get_prop(data, "nodes", "data")?;
-    let nodes = nodes.as_object().ok_or("nodes is not an object")?;
+        mappings.insert(key, graph.add_node(node));
+    }
 
-    for (key, node) in nodes.iter() {
-        let node = node.as_object().ok_or("node is not an object")?;
+    for link in &links {
+        graph.add_edge(mappings[&link.from], mappings[&link.to], link.input);
+    }
 
-        let title = get_prop(node, "title", "node")?;
-        let title = as_str(title, "node title")?;
+    let live = live_nodes(&sinks, &links);
 
-        let graph_id = graph.add_node(match title {
-            "Input" => {
-                let location = get_prop(node, "location", "input")?;
-                let location = as_uint(location, "input location")?;
+    let mut warnings = Vec::new();
+    for key in document.nodes.keys() {
+        if !live.contains(key) {
+            warnings.push(
+                Diagnostic::warning("node is not connected to any output").at_node(key.as_str())
+            );
+        }
+    }
 
-                let data_type = get_prop(node, "type", "input")?;
-                let data_type = as_str(data_type, "input type")?;
+    Ok(ParsedGraph {
+        graph: graph,
+        stage: stage,
+        warnings: warnings,
+        nodes: kinds,
+        edges: links,
+        types: types,
+    })
+}
 
-                Node::Input(location as u32, as_typename(data_type, "input")?)
-            },
-            "Output" => {
-                let location = get_prop(node, "location", "output")?;
-                let location = as_uint(location, "output location")?;
+// Infer the types of a document that may not build yet, along with every
+// diagnostic found on the way
+fn infer_partial(document: &GraphDocument, unread: &HashSet<String>) -> (BTreeMap<String, NodeTypes>, Vec<Diagnostic>) {
+    let stage = document.stage.shader_type();
 
-                let data_type = get_prop(node, "type", "output")?;
-                let data_type = as_str(data_type, "output type")?;
+    let (nodes, mut diagnostics) = collect_nodes(document, &stage);
+    let (links, fed, errors) = collect_edges(document, &nodes, unread);
+    diagnostics.extend(errors);
 
-                Node::Output(location as u32, as_typename(data_type, "output")?)
-            },
+    let kinds: BTreeMap<_, _> = nodes.iter()
+        .map(|(key, node)| (key.clone(), Kind::from_node(node)))
+        .collect();
 
-            "Construct" => {
-                let data_type = get_prop(node, "type", "input")?;
-                let data_type = as_str(data_type, "construct type")?;
+    diagnostics.extend(validate(&kinds, &links, &fed));
 
-                Node::Construct(as_typename(data_type, "construct")?)
-            },
-            "Extract" => {
-                let index = get_prop(node, "index", "extract")?;
-                let index = as_uint(index, "extract index")?;
+    let (types, errors) = infer_types(&kinds, &links);
+    diagnostics.extend(errors);
 
-                Node::Extract(index as u32)
-            },
+    (types, diagnostics)
+}
 
-            "Constant" => {
-                let data_type = get_prop(node, "type", "constant")?;
-                let data_type = as_str(data_type, "constant type")?;
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, Value};
+
+    use super::{as_float, as_matrix, infer_input, parse_input};
+
+    fn passthrough(stage: &str, data_type: &str) -> String {
+        format!(r#"{{
+            "version": 1,
+            "stage": "{}",
+            "nodes": {{
+                "0": {{ "title": "Input", "location": 0, "type": "{}" }},
+                "1": {{ "title": "Output", "location": 0, "type": "{}" }}
+            }},
+            "edges": [ {{ "from": 0, "to": 1, "input": 0 }} ]
+        }}"#, stage, data_type, data_type)
+    }
 
-                let value = get_prop(node, "value", "constant")?;
+    fn errors(input: String) -> Vec<String> {
+        match parse_input(input) {
+            Ok(_) => Vec::new(),
+            Err(diags) => diags.into_iter().map(|diag| diag.message).collect(),
+        }
+    }
 
-                Node::Constant(match as_typename(data_type, "constant")? {
-                    &TypeName::Bool => {
-                        let value = value.as_bool().ok_or("constant value is not a boolean")?;
-                        TypedValue::Bool(value)
-                    },
+    #[test]
+    fn dead_branches_are_reported() {
+        // 2 and 3 feed each other but never reach the output
+        let input = String::from(r#"{
+            "version": 1,
+            "stage": "fragment",
+            "nodes": {
+                "0": { "title": "Input", "location": 0, "type": "vec4" },
+                "1": { "title": "Output", "location": 0, "type": "vec4" },
+                "2": { "title": "Normalize" },
+                "3": { "title": "Floor" }
+            },
+            "edges": [
+                { "from": 0, "to": 1, "input": 0 },
+                { "from": 0, "to": 2, "input": 0 },
+                { "from": 2, "to": 3, "input": 0 }
+            ]
+        }"#);
+
+        let parsed = match parse_input(input) {
+            Ok(parsed) => parsed,
+            Err(diags) => panic!("{:?}", diags),
+        };
+
+        let unused: Vec<_> = parsed.warnings.iter()
+            .filter(|diag| diag.message == "node is not connected to any output")
+            .filter_map(|diag| diag.node.clone())
+            .collect();
+        assert_eq!(unused, vec![String::from("2"), String::from("3")]);
+    }
 
-                    &TypeName::Int(signed) => if signed {
-                        TypedValue::Int(
-                            as_int(&value, "constant value")?
-                        )
-                    } else {
-                        TypedValue::UInt(
-                            as_uint(&value, "constant value")?
-                        )
-                    },
+    #[test]
+    fn fragment_accepts_flat_inputs() {
+        for data_type in &["int", "uvec3", "ivec4"] {
+            assert_eq!(errors(passthrough("fragment", data_type)), Vec::<String>::new());
+        }
 
-                    &TypeName::Float(precision) => if precision {
-                        TypedValue::Double(
-                            as_double(&value, "constant value")?
-                        )
-                    } else {
-                        TypedValue::Float(
-                            as_float(&value, "constant value")?
-                        )
-                    },
+        assert_eq!(errors(passthrough("fragment", "dvec2")), vec![
+            String::from("Fragment output 0 cannot be a double"),
+        ]);
+    }
 
-                    &TypeName::Vec(size, scalar) => {
-                        let value = value.as_array().ok_or("constant value is not an array")?;
-                        if value.len() != size as usize {
-                            return Err(format!("Wrong array length for {}", data_type));
-                        }
-
-                        match scalar {
-                            &TypeName::Bool => {
-                                let res: Result<Vec<_>, _> =
-                                    value.into_iter()
-                                        .map(|v| v.as_bool().ok_or("vector element is not a boolean"))
-                                        .collect();
-
-                                let values = res?;
-                                match size {
-                                    2 => TypedValue::BVec2(
-                                        values[0], values[1]
-                                    ),
-                                    3 => TypedValue::BVec3(
-                                        values[0], values[1], values[2]
-                                    ),
-                                    4 => TypedValue::BVec4(
-                                        values[0], values[1], values[2], values[3]
-                                    ),
-                                    _ => return Err(format!("Wrong bvec size: {}", size))
-                                }
-                            },
-
-                            &TypeName::Int(signed) => if signed {
-                                let res: Result<Vec<_>, _> =
-                                    value.into_iter()
-                                        .map(|v| as_int(&v, "vector element"))
-                                        .collect();
-
-                                let values = res?;
-                                match size {
-                                    2 => TypedValue::IVec2(
-                                        values[0], values[1]
-                                    ),
-                                    3 => TypedValue::IVec3(
-                                        values[0], values[1], values[2]
-                                    ),
-                                    4 => TypedValue::IVec4(
-                                        values[0], values[1], values[2], values[3]
-                                    ),
-                                    _ => return Err(format!("Wrong ivec size: {}", size))
-                                }
-                            } else {
-                                let res: Result<Vec<_>, _> =
-                                    value.into_iter()
-                                        .map(|v| as_uint(&v, "vector element"))
-                                        .collect();
-
-                                let values = res?;
-                                match size {
-                                    2 => TypedValue::UVec2(
-                                        values[0], values[1]
-                                    ),
-                                    3 => TypedValue::UVec3(
-                                        values[0], values[1], values[2]
-                                    ),
-                                    4 => TypedValue::UVec4(
-                                        values[0], values[1], values[2], values[3]
-                                    ),
-                                    _ => return Err(format!("Wrong uvec size: {}", size))
-                                }
-                            },
-
-                            &TypeName::Float(precision) => if precision {
-                                let res: Result<Vec<_>, _> =
-                                    value.into_iter()
-                                        .map(|v| as_double(&v, "vector element"))
-                                        .collect();
-
-                                let values = res?;
-                                match size {
-                                    2 => TypedValue::DVec2(
-                                        values[0], values[1]
-                                    ),
-                                    3 => TypedValue::DVec3(
-                                        values[0], values[1], values[2]
-                                    ),
-                                    4 => TypedValue::DVec4(
-                                        values[0], values[1], values[2], values[3]
-                                    ),
-                                    _ => return Err(format!("Wrong dvec size: {}", size))
-                                }
-                            } else {
-                                let res: Result<Vec<_>, _> =
-                                    value.into_iter()
-                                        .map(|v| as_float(&v, "vector element"))
-                                        .collect();
-
-                                let values = res?;
-                                match size {
-                                    2 => TypedValue::Vec2(
-                                        values[0], values[1]
-                                    ),
-                                    3 => TypedValue::Vec3(
-                                        values[0], values[1], values[2]
-                                    ),
-                                    4 => TypedValue::Vec4(
-                                        values[0], values[1], values[2], values[3]
-                                    ),
-                                    _ => return Err(format!("Wrong vec size: {}", size))
-                                }
-                            },
-
-                            _ => return Err(format!("Wrong vector scalar type: {:?}", *scalar)),
-                        }
-                    },
+    #[test]
+    fn stages_reject_boolean_interfaces() {
+        assert_eq!(errors(passthrough("fragment", "bool")), vec![
+            String::from("Fragment input 0 cannot be a boolean"),
+            String::from("Fragment output 0 cannot be a boolean"),
+        ]);
+        assert_eq!(errors(passthrough("vertex", "bvec2")), vec![
+            String::from("Vertex input 0 cannot be a boolean"),
+            String::from("Vertex output 0 cannot be a boolean"),
+        ]);
+    }
 
-                    // TODO: Support matrix constants
+    #[test]
+    fn every_node_error_is_reported() {
+        let input = String::from(r#"{
+            "version": 1,
+            "stage": "fragment",
+            "nodes": {
+                "0": { "title": "Constant", "type": "vec2", "value": [1.0] },
+                "1": { "title": "Teleport" },
+                "2": { "title": "Output", "location": 0, "type": "vec4" }
+            },
+            "edges": [ { "from": 3, "to": 2, "input": 0 } ]
+        }"#);
+
+        assert_eq!(errors(input), vec![
+            String::from("Wrong array length for vec2"),
+            String::from("Unimplemented node \"Teleport\""),
+            String::from("edge origin node is undefined"),
+        ]);
+    }
 
-                    _ => return Err(format!("Unknown constant type {:?}", data_type))
-                })
+    #[test]
+    fn shape_errors_do_not_hide_the_rest() {
+        // 0 can't be read, its edge isn't reported again
+        let input = String::from(r#"{
+            "version": 1,
+            "stage": "fragment",
+            "nodes": {
+                "0": { "title": "Input", "type": "vec4" },
+                "1": { "title": "Teleport" },
+                "2": { "title": "Output", "location": 0, "type": "vec4" }
             },
+            "edges": [
+                { "from": 0, "to": 2, "input": 0 },
+                { "from": 1, "to": 2 },
+                { "from": 3, "to": 2, "input": 0 }
+            ]
+        }"#);
+
+        assert_eq!(errors(input), vec![
+            String::from("interface: missing field \"location\""),
+            String::from("edge: missing field \"input\""),
+            String::from("Unimplemented node \"Teleport\""),
+            String::from("edge origin node is undefined"),
+        ]);
+    }
 
-            _ => Node::from_string(title).ok_or(format!("Unimplemented node {:?}", title))?,
-        });
+    #[test]
+    fn unread_nodes_still_connect_their_edges() {
+        let input = String::from(r#"{
+            "version": 1,
+            "stage": "fragment",
+            "nodes": {
+                "0": { "title": "Input", "type": "vec4" },
+                "1": { "title": "Output", "location": 0, "type": "vec4" }
+            },
+            "edges": [ { "from": 0, "to": 1, "input": 0 } ]
+        }"#);
 
-        mappings.insert(key.clone(), graph_id);
+        assert_eq!(errors(input), vec![
+            String::from("interface: missing field \"location\""),
+        ]);
     }
 
-    let edges = get_prop(data, "edges", "data")?;
-    let edges = edges.as_array().ok_or("edges is not an array")?;
+    #[test]
+    fn failed_nodes_still_connect_their_edges() {
+        let input = r#"{
+            "version": 1,
+            "stage": "fragment",
+            "nodes": {
+                "0": { "title": "Teleport" },
+                "1": { "title": "Output", "location": 0, "type": "vec4" }
+            },
+            "edges": [ { "from": 0, "to": 1, "input": 0 } ]
+        }"#;
 
-    for edge in edges.iter() {
-        let edge = edge.as_object().ok_or("edge is not an object")?;
+        let (_, diags) = infer_input(input).unwrap();
+        let messages: Vec<_> = diags.iter().map(|diag| diag.message.as_str()).collect();
+        assert_eq!(messages, vec!["Unimplemented node \"Teleport\""]);
+    }
+
+    #[test]
+    fn matrices_are_column_major() {
+        let value: Value = from_str("[[1, 2, 3], [4, 5, 6], [7, 8, 9]]").unwrap();
+        let values = as_matrix(&value, 3, |v| as_float(v, "matrix element")).unwrap();
+        assert_eq!(values, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
 
-        let from = get_prop(edge, "from", "edge")?;
-        let from = from.as_u64().ok_or("edge origin is not a number")?;
+        let value: Value = from_str("[[1, 2], [3, 4], [5, 6]]").unwrap();
+        assert_eq!(as_matrix(&value, 2, |v| as_float(v, "matrix element")).unwrap_err(), "Wrong column count for mat2: 3");
 
-        let to = get_prop(edge, "to", "edge")?;
-        let to = to.as_u64().ok_or("edge destination is not a number")?;
+        let value: Value = from_str("[[1, 2], [3]]").unwrap();
+        assert_eq!(as_matrix(&value, 2, |v| as_float(v, "matrix element")).unwrap_err(), "Wrong column length for mat2: 1");
+    }
 
-        let input = get_prop(edge, "input", "edge")?;
-        let input = input.as_u64().ok_or("edge input is not a number")?;
+    #[test]
+    fn type_errors_point_at_the_edge() {
+        let input = String::from(r#"{
+            "version": 1,
+            "stage": "fragment",
+            "nodes": {
+                "0": { "title": "Input", "location": 0, "type": "vec4" },
+                "1": { "title": "Input", "location": 1, "type": "vec3" },
+                "2": { "title": "Add" },
+                "3": { "title": "Output", "location": 0, "type": "vec4" }
+            },
+            "edges": [
+                { "from": 0, "to": 2, "input": 0 },
+                { "from": 1, "to": 2, "input": 1 },
+                { "from": 2, "to": 3, "input": 0 }
+            ]
+        }"#);
+
+        let diags = parse_input(input).err().unwrap();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].message, "expected vec4, found vec3");
+        assert_eq!(diags[0].node, Some(String::from("2")));
+        assert_eq!(diags[0].pin, Some(1));
+        assert_eq!(diags[0].edge, Some(1));
+    }
 
-        graph.add_edge(
-            *mappings.get(&format!("{}", from)).ok_or("edge origin node is undefined")?,
-            *mappings.get(&format!("{}", to)).ok_or("edge destination node is undefined")?,
-            input as u32,
-        );
+    #[test]
+    fn structure_errors_are_reported_together() {
+        let input = String::from(r#"{
+            "version": 1,
+            "stage": "fragment",
+            "nodes": {
+                "0": { "title": "Input", "location": 0, "type": "vec4" },
+                "1": { "title": "Normalize" },
+                "2": { "title": "Floor" },
+                "3": { "title": "Add" },
+                "4": { "title": "Output", "location": 0, "type": "vec4" }
+            },
+            "edges": [
+                { "from": 1, "to": 2, "input": 0 },
+                { "from": 2, "to": 1, "input": 0 },
+                { "from": 0, "to": 3, "input": 0 },
+                { "from": 0, "to": 3, "input": 0 },
+                { "from": 0, "to": 4, "input": 3 }
+            ]
+        }"#);
+
+        assert_eq!(errors(input), vec![
+            String::from("input 0 is already connected by edge 2"),
+            String::from("node has 1 inputs, edge targets input 3"),
+            String::from("input 1 is not connected"),
+            String::from("input 0 is not connected"),
+            String::from("graph contains a cycle through nodes 1 -> 2 -> 1"),
+        ]);
     }
 
-    Ok(graph)
+    #[test]
+    fn unconnected_inputs_are_inferred() {
+        let input = r#"{
+            "version": 1,
+            "stage": "fragment",
+            "nodes": {
+                "0": { "title": "Input", "location": 0, "type": "vec4" },
+                "1": { "title": "Add" },
+                "2": { "title": "Output", "location": 0, "type": "vec4" }
+            },
+            "edges": [ { "from": 0, "to": 1, "input": 0 } ]
+        }"#;
+
+        let (types, diags) = infer_input(input).unwrap();
+
+        let messages: Vec<_> = diags.iter().map(|diag| diag.message.as_str()).collect();
+        assert_eq!(messages, vec!["input 1 is not connected", "input 0 is not connected"]);
+
+        let text = |ty: &Option<_>| ty.map(|ty: ::typeck::Type| format!("{}", ty));
+        let add = &types["1"];
+        assert_eq!(text(&add.output), None);
+        assert_eq!(add.inputs.iter().map(text).collect::<Vec<_>>(), vec![Some(String::from("vec4")); 2]);
+
+        let output = &types["2"];
+        assert_eq!(output.inputs.iter().map(text).collect::<Vec<_>>(), vec![Some(String::from("vec4"))]);
+    }
 }
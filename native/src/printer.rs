@@ -1,30 +1,42 @@
-use std::fmt;
 use std::mem::transmute;
-
-use rasen::Module as Mod;
-use rasen::glsl::*;
-
-use spirv_utils::instruction::Instruction as Inst;
-use spirv_utils::instruction::Decoration as Deco;
-use spirv_utils::desc::{
-    Id, ResultId,
-    TypeId, ValueId,
-};
+use std::iter::repeat;
+use std::collections::{HashMap, HashSet};
 
 use serde_json::{
-    to_string, to_value,
+    to_value,
     Value, Map
 };
 
-enum Operand {
+use grammar::*;
+
+pub const MAGIC_NUMBER: u32 = 0x07230203;
+
+/// An operand of a decoded instruction
+pub enum Operand {
+    /// Text of a comment line
     Text(String),
+    /// Reference to the result of another instruction
     Id(u32),
+    /// Reference to a type declaration
     Type(u32),
+    /// Name of an enumerant, such as a storage class or decoration
+    Enum(String),
+    /// Literal string
     LitString(String),
+    /// 32-bit unsigned literal
     LitInt(u32),
+    /// 32-bit signed literal
+    LitSInt(i32),
+    /// 64-bit unsigned literal
+    LitLong(u64),
+    /// 64-bit signed literal
+    LitSLong(i64),
+    /// 32-bit float literal
     LitFloat(f32),
+    /// 64-bit float literal
     LitDouble(f64),
-    ExtInst(GLSL),
+    /// Name of an extended instruction
+    ExtInst(String),
 }
 
 macro_rules! insert_operand {
@@ -34,18 +46,36 @@ macro_rules! insert_operand {
     }
 }
 
-fn new_operand(operand: Operand) -> Value {
+// Result id as printed, either its friendly name or its number
+fn id_value(names: &Names, id: u32) -> Value {
+    match names.get(&id) {
+        Some(name) => Value::String(name.clone()),
+        None => Value::U64(id as u64),
+    }
+}
+
+fn id_text(names: &Names, id: u32) -> String {
+    match names.get(&id) {
+        Some(name) => format!("%{}", name),
+        None => format!("%{}", id),
+    }
+}
+
+fn new_operand(names: &Names, operand: &Operand) -> Value {
     let mut res = Map::new();
 
-    match operand {
+    match *operand {
         Operand::Text(ref val) => {
             insert_operand!(res, "Text", val);
         },
         Operand::Id(val) => {
-            insert_operand!(res, "Id", val);
+            insert_operand!(res, "Id", id_value(names, val));
         },
         Operand::Type(val) => {
-            insert_operand!(res, "Type", val);
+            insert_operand!(res, "Type", id_value(names, val));
+        },
+        Operand::Enum(ref val) => {
+            insert_operand!(res, "Enum", val);
         },
         Operand::LitString(ref val) => {
             insert_operand!(res, "String", val);
@@ -53,332 +83,698 @@ fn new_operand(operand: Operand) -> Value {
         Operand::LitInt(val) => {
             insert_operand!(res, "Int", val);
         },
+        Operand::LitSInt(val) => {
+            insert_operand!(res, "Int", val);
+        },
+        Operand::LitLong(val) => {
+            insert_operand!(res, "Int", val);
+        },
+        Operand::LitSLong(val) => {
+            insert_operand!(res, "Int", val);
+        },
         Operand::LitFloat(val) => {
-            insert_operand!(res, "Float", val as f64);
+            // Widen through the decimal text so 0.1 stays 0.1 in the JSON
+            let wide = format!("{}", val).parse().unwrap_or(val as f64);
+            insert_operand!(res, "Float", wide);
         },
         Operand::LitDouble(val) => {
             insert_operand!(res, "Double", val);
         },
         Operand::ExtInst(ref val) => {
-            insert_operand!(res, "ExtInst", format!("{:?}", val));
+            insert_operand!(res, "ExtInst", val);
         }
     }
 
     Value::Object(res)
 }
 
-trait AsOperand {
-    fn as_op(&self) -> Operand;
+/// A decoded instruction, or a comment line of the listing
+pub struct Instruction {
+    /// Opcode, 0 for comments
+    pub opcode: u16,
+    /// Name of the opcode, `;` for comments
+    pub class: String,
+    /// Id the instruction defines, if any
+    pub result_id: Option<u32>,
+    /// Operands following the result id
+    pub operands: Vec<Operand>,
 }
 
-impl<T: fmt::Debug> AsOperand for T {
-    default fn as_op(&self) -> Operand {
-        Operand::Text(format!("{:?}", self))
+impl Instruction {
+    fn comment(text: String) -> Instruction {
+        Instruction {
+            opcode: 0,
+            class: String::from(";"),
+            result_id: None,
+            operands: vec![
+                Operand::Text(text),
+            ],
+        }
     }
-}
 
-impl AsOperand for Id {
-    fn as_op(&self) -> Operand {
-        Operand::Id(self.0)
+    /// Whether this is a comment line rather than an instruction
+    pub fn is_comment(&self) -> bool {
+        self.class == ";"
     }
 }
-impl AsOperand for ResultId {
-    fn as_op(&self) -> Operand {
-        Operand::Id(self.0)
-    }
+
+/// Header words of a module
+pub struct Header {
+    /// SPIR-V version number
+    pub version: u32,
+    /// Magic number of the tool that generated the module
+    pub generator: u32,
+    /// Upper bound of the result ids
+    pub bound: u32,
+    /// Reserved instruction schema
+    pub schema: u32,
 }
-impl AsOperand for ValueId {
-    fn as_op(&self) -> Operand {
-        Operand::Id(self.0)
+
+// Friendly names given to result ids, see names::friendly_names
+pub type Names = HashMap<u32, String>;
+
+/// A decoded module, ready to be printed
+pub struct Listing {
+    /// Module header
+    pub header: Header,
+    /// Instructions in module order
+    pub instructions: Vec<Instruction>,
+    /// Names printed in place of result ids
+    pub names: Names,
+}
+
+impl Listing {
+    /// JSON listing of the module, as displayed by the editor
+    pub fn to_value(&self) -> Value {
+        listing_value(self)
+    }
+
+    /// Textual assembly in the format of spirv-dis
+    pub fn to_text(&self) -> String {
+        to_spvasm_text(self)
     }
 }
 
-impl AsOperand for TypeId {
-    fn as_op(&self) -> Operand {
-        Operand::Type(self.0)
+#[derive(Clone, Copy)]
+enum Scalar {
+    Int(u32, bool),
+    Float(u32),
+}
+
+// Scalar types declared so far, used to decode constant literals
+type TypeTable = HashMap<u32, Scalar>;
+
+fn convert_constant(types: &TypeTable, result_type: u32, val: &[u32]) -> Result<Operand, String> {
+    let scalar = types.get(&result_type)
+        .ok_or(format!("Constant type %{} is not a scalar type", result_type))?;
+
+    Ok(match (*scalar, val.len()) {
+        (Scalar::Int(32, true), 1) => Operand::LitSInt(val[0] as i32),
+        (Scalar::Int(32, false), 1) => Operand::LitInt(val[0]),
+        (Scalar::Int(64, signed), 2) => {
+            let value = (val[0] as u64) | ((val[1] as u64) << 32);
+            if signed {
+                Operand::LitSLong(value as i64)
+            } else {
+                Operand::LitLong(value)
+            }
+        },
+
+        (Scalar::Float(32), 1) => Operand::LitFloat(unsafe {
+            transmute(val[0])
+        }),
+        (Scalar::Float(64), 2) => Operand::LitDouble(unsafe {
+            let mut a: [u32; 2] = Default::default();
+            a.copy_from_slice(val);
+            transmute(a)
+        }),
+
+        _ => return Err(format!("Unexpected constant size {} for type %{}", val.len(), result_type))
+    })
+}
+
+#[derive(Default)]
+struct Context {
+    types: TypeTable,
+    type_ids: HashSet<u32>,
+    glsl_sets: HashSet<u32>,
+}
+
+pub fn decode_string(words: &[u32]) -> Result<(String, usize), String> {
+    let mut bytes = Vec::new();
+
+    for (index, word) in words.iter().enumerate() {
+        for shift in 0..4 {
+            let byte = ((word >> (shift * 8)) & 0xff) as u8;
+            if byte == 0 {
+                let string = String::from_utf8(bytes)
+                    .map_err(|err| format!("Invalid string literal: {}", err))?;
+                return Ok((string, index + 1));
+            }
+
+            bytes.push(byte);
+        }
     }
+
+    Err(String::from("Unterminated string literal"))
 }
 
-impl AsOperand for str {
-    fn as_op(&self) -> Operand {
-        Operand::Text(String::from(self))
+struct Decoder<'a> {
+    ctx: &'a Context,
+    name: &'static str,
+    words: &'a [u32],
+    cursor: usize,
+    inst: Instruction,
+}
+
+impl<'a> Decoder<'a> {
+    fn has_words(&self) -> bool {
+        self.cursor < self.words.len()
+    }
+
+    fn next_word(&mut self) -> Result<u32, String> {
+        let word = *self.words.get(self.cursor)
+            .ok_or(format!("{} is missing operands", self.name))?;
+        self.cursor += 1;
+        Ok(word)
+    }
+
+    fn result_type(&self) -> Option<u32> {
+        self.inst.operands.iter()
+            .filter_map(|op| match *op {
+                Operand::Type(id) => Some(id),
+                _ => None,
+            })
+            .next()
+    }
+
+    fn operand(&mut self, kind: Kind) -> Result<(), String> {
+        match kind {
+            Kind::IdResultType => {
+                let id = self.next_word()?;
+                self.inst.operands.push(Operand::Type(id));
+            },
+            Kind::IdResult => {
+                let id = self.next_word()?;
+                self.inst.result_id = Some(id);
+            },
+            Kind::IdRef => {
+                let id = self.next_word()?;
+                self.inst.operands.push(if self.ctx.type_ids.contains(&id) {
+                    Operand::Type(id)
+                } else {
+                    Operand::Id(id)
+                });
+            },
+
+            Kind::LiteralInteger => {
+                let value = self.next_word()?;
+                self.inst.operands.push(Operand::LitInt(value));
+            },
+            Kind::LiteralString => {
+                let (string, len) = decode_string(&self.words[self.cursor..])?;
+                self.cursor += len;
+                self.inst.operands.push(Operand::LitString(string));
+            },
+            Kind::LiteralContextDependentNumber => {
+                let result_type = self.result_type()
+                    .ok_or(format!("{} has no result type", self.name))?;
+
+                let value = convert_constant(&self.ctx.types, result_type, &self.words[self.cursor..])?;
+                self.cursor = self.words.len();
+                self.inst.operands.push(value);
+            },
+            Kind::LiteralExtInstInteger => {
+                let set = match self.inst.operands.last() {
+                    Some(&Operand::Id(set)) => Some(set),
+                    _ => None,
+                };
+
+                let value = self.next_word()?;
+                let name = set
+                    .and_then(|set| if self.ctx.glsl_sets.contains(&set) { glsl_instruction(value) } else { None });
+
+                self.inst.operands.push(match name {
+                    Some(name) => Operand::ExtInst(String::from(name)),
+                    None => Operand::LitInt(value),
+                });
+            },
+            Kind::LiteralSpecConstantOpInteger => {
+                let value = self.next_word()?;
+                let desc = instruction(value as u16)
+                    .ok_or(format!("Unknown opcode {} in {}", value, self.name))?;
+                self.inst.operands.push(Operand::Enum(String::from(&desc.name[2..])));
+            },
+
+            Kind::PairLiteralIntegerIdRef => {
+                self.operand(Kind::LiteralInteger)?;
+                self.operand(Kind::IdRef)?;
+            },
+            Kind::PairIdRefLiteralInteger => {
+                self.operand(Kind::IdRef)?;
+                self.operand(Kind::LiteralInteger)?;
+            },
+            Kind::PairIdRefIdRef => {
+                self.operand(Kind::IdRef)?;
+                self.operand(Kind::IdRef)?;
+            },
+
+            Kind::Enum(kind) => {
+                let value = self.next_word()?;
+                self.enumerant(kind, value)?;
+            },
+        }
+
+        Ok(())
+    }
+
+    fn enumerant(&mut self, kind: EnumKind, value: u32) -> Result<(), String> {
+        if !kind.is_bitmask() || value == 0 {
+            let enumerant = kind.enumerant(value)
+                .ok_or(format!("Unknown {:?} value {} in {}", kind, value, self.name))?;
+
+            self.inst.operands.push(Operand::Enum(String::from(enumerant.name)));
+            for param in enumerant.params {
+                self.operand(*param)?;
+            }
+
+            return Ok(());
+        }
+
+        let enumerants: Vec<_> = kind.enumerants().iter()
+            .filter(|e| e.value != 0 && value & e.value == e.value)
+            .collect();
+
+        let known = enumerants.iter().fold(0, |acc, e| acc | e.value);
+        if known != value {
+            return Err(format!("Unknown {:?} bits {:#x} in {}", kind, value & !known, self.name));
+        }
+
+        let names: Vec<_> = enumerants.iter().map(|e| e.name).collect();
+        self.inst.operands.push(Operand::Enum(names.join("|")));
+
+        for enumerant in enumerants {
+            for param in enumerant.params {
+                self.operand(*param)?;
+            }
+        }
+
+        Ok(())
     }
 }
-impl AsOperand for String {
-    fn as_op(&self) -> Operand {
-        Operand::LitString(self.clone())
+
+fn decode_instruction(ctx: &mut Context, opcode: u16, words: &[u32]) -> Result<Instruction, String> {
+    let desc = match instruction(opcode) {
+        Some(desc) => desc,
+        None => return Ok(Instruction {
+            opcode: opcode,
+            class: format!("Op{}", opcode),
+            result_id: None,
+            operands: words.iter()
+                .map(|word| Operand::LitInt(*word))
+                .collect(),
+        }),
+    };
+
+    let inst = {
+        let mut decoder = Decoder {
+            ctx: ctx,
+            name: desc.name,
+            words: words,
+            cursor: 0,
+            inst: Instruction {
+                opcode: opcode,
+                class: String::from(desc.name),
+                result_id: None,
+                operands: Vec::new(),
+            },
+        };
+
+        for operand in desc.operands {
+            match operand.quantifier {
+                Quantifier::One => decoder.operand(operand.kind)?,
+                Quantifier::Optional => if decoder.has_words() {
+                    decoder.operand(operand.kind)?;
+                },
+                Quantifier::Variadic => while decoder.has_words() {
+                    decoder.operand(operand.kind)?;
+                },
+            }
+        }
+
+        if decoder.has_words() {
+            return Err(format!("{} has {} unexpected trailing words", desc.name, words.len() - decoder.cursor));
+        }
+
+        decoder.inst
+    };
+
+    match (opcode, inst.result_id) {
+        (21, Some(id)) => {
+            ctx.types.insert(id, Scalar::Int(words[1], words[2] != 0));
+        },
+        (22, Some(id)) => {
+            ctx.types.insert(id, Scalar::Float(words[1]));
+        },
+        (11, Some(id)) => if let Some(&Operand::LitString(ref name)) = inst.operands.last() {
+            if name == GLSL_STD_450 {
+                ctx.glsl_sets.insert(id);
+            }
+        },
+        _ => {},
+    }
+
+    if is_type_declaration(opcode) {
+        if let Some(id) = inst.result_id {
+            ctx.type_ids.insert(id);
+        }
     }
+
+    Ok(inst)
 }
 
-impl AsOperand for bool {
-    fn as_op(&self) -> Operand {
-        Operand::LitInt(if *self {1} else {0})
+pub fn words_from_bytes(bytes: &[u8]) -> Result<Vec<u32>, String> {
+    if bytes.len() % 4 != 0 {
+        return Err(format!("SPIR-V module length {} is not a multiple of 4", bytes.len()));
+    }
+
+    let little: Vec<u32> = bytes.chunks(4)
+        .map(|w| (w[0] as u32) | ((w[1] as u32) << 8) | ((w[2] as u32) << 16) | ((w[3] as u32) << 24))
+        .collect();
+
+    match little.first() {
+        Some(&MAGIC_NUMBER) => Ok(little),
+        Some(&magic) if magic.swap_bytes() == MAGIC_NUMBER => Ok(
+            little.into_iter()
+                .map(u32::swap_bytes)
+                .collect()
+        ),
+        _ => Err(String::from("Input is not a SPIR-V module")),
     }
 }
 
-impl AsOperand for u32 {
-    fn as_op(&self) -> Operand {
-        Operand::LitInt(*self)
+pub fn decode_words(words: &[u32]) -> Result<Listing, String> {
+    if words.len() < 5 {
+        return Err(String::from("SPIR-V module is too short"));
+    }
+    if words[0] != MAGIC_NUMBER {
+        return Err(format!("Invalid SPIR-V magic number {:#010x}", words[0]));
+    }
+
+    let header = Header {
+        version: words[1],
+        generator: words[2],
+        bound: words[3],
+        schema: words[4],
+    };
+
+    let mut ctx = Context::default();
+    let mut instructions = Vec::new();
+
+    let mut cursor = 5;
+    while cursor < words.len() {
+        let count = (words[cursor] >> 16) as usize;
+        let opcode = (words[cursor] & 0xffff) as u16;
+
+        if count == 0 || cursor + count > words.len() {
+            return Err(format!("Invalid word count {} at word {}", count, cursor));
+        }
+
+        instructions.push(
+            decode_instruction(&mut ctx, opcode, &words[cursor + 1 .. cursor + count])?
+        );
+
+        cursor += count;
     }
+
+    Ok(Listing {
+        header: header,
+        instructions: instructions,
+        names: Names::new(),
+    })
+}
+
+fn generator_name(generator: u32) -> String {
+    let tool = match generator >> 16 {
+        0 => "Khronos",
+        1 => "LunarG",
+        2 => "Valve",
+        3 => "Codeplay",
+        4 => "NVIDIA",
+        5 => "ARM",
+        6 => "Khronos LLVM/SPIR-V Translator",
+        7 => "Khronos SPIR-V Tools Assembler",
+        8 => "Khronos Glslang Reference Front End",
+        _ => "Unknown",
+    };
+
+    format!("{}; {}", tool, generator & 0xffff)
+}
+
+pub fn header_comments(header: &Header) -> Vec<Instruction> {
+    vec![
+        Instruction::comment(String::from("SPIR-V")),
+        Instruction::comment(format!("Version: {}.{}", (header.version >> 16) & 0xff, (header.version >> 8) & 0xff)),
+        Instruction::comment(format!("Generator: {}", generator_name(header.generator))),
+        Instruction::comment(format!("Bound: {}", header.bound)),
+        Instruction::comment(format!("Schema: {}", header.schema)),
+    ]
 }
 
-fn new_instruction<V>(class: &str, result_id: Option<u32>, operands: V) -> Result<Value, String> where V: IntoIterator<Item=Operand> {
+fn instruction_value(names: &Names, inst: &Instruction) -> Value {
     let mut res = Map::new();
 
-    res.insert(String::from("class"), Value::String(String::from(class)));
+    res.insert(String::from("class"), Value::String(inst.class.clone()));
 
-    if let Some(id) = result_id {
-        res.insert(String::from("result_id"), Value::U64(id as u64));
+    if let Some(id) = inst.result_id {
+        res.insert(String::from("result_id"), id_value(names, id));
     }
 
     res.insert(String::from("operands"), Value::Array(
-        operands.into_iter()
-            .map(new_operand)
+        inst.operands.iter()
+            .map(|op| new_operand(names, op))
             .collect()
     ));
 
-    Ok(Value::Object(res))
+    Value::Object(res)
 }
 
-fn convert_instruction(inst: &Inst) -> Result<Value, String> {
-    match inst {
-        &Inst::Capability { capability } => new_instruction(
-            "OpCapability",
-            None,
-            Some(capability.as_op())
-        ),
-        &Inst::MemoryModel { addressing_model, memory_model } => new_instruction(
-            "OpMemoryModel",
-            None,
-            vec![
-                addressing_model.as_op(),
-                memory_model.as_op(),
-            ]
-        ),
-        &Inst::EntryPoint { execution_model, func, ref name, ref interface } => new_instruction(
-            "OpEntryPoint",
-            None,
-            vec![
-                execution_model.as_op(),
-                func.as_op(),
-                name.as_op(),
-            ].into_iter()
-                .chain(
-                    interface.iter()
-                        .map(|id| id.as_op())
-                )
-        ),
-        &Inst::ExecutionMode { entry, ref execution_mode } => new_instruction(
-            "OpExecutionMode",
-            None,
-            vec![
-                entry.as_op(),
-                execution_mode.as_op(),
-            ]
-        ),
-        &Inst::Decorate { target, ref decoration } => new_instruction(
-            "OpDecorate",
-            None,
-            vec![
-                target.as_op(),
-            ].into_iter()
-                .chain(
-                    match decoration {
-                        &Deco::Location(loc) => vec![
-                            "Location".as_op(),
-                            Operand::LitInt(loc),
-                        ],
-                        _ => vec![]
-                    }
-                )
-        ),
+pub fn listing_value(listing: &Listing) -> Value {
+    let instructions = header_comments(&listing.header).iter()
+        .chain(listing.instructions.iter())
+        .map(|inst| instruction_value(&listing.names, inst))
+        .collect();
 
-        &Inst::Function { result_type, result_id, function_control, fn_ty } => new_instruction(
-            "OpFunction",
-            Some(result_id.0),
-            vec![
-                result_type.as_op(),
-                format!("{}", function_control).as_str().as_op(),
-                fn_ty.as_op(),
-            ]
-        ),
+    let mut header = Map::new();
+    header.insert(String::from("magic"), Value::U64(MAGIC_NUMBER as u64));
+    header.insert(String::from("version"), Value::U64(listing.header.version as u64));
+    header.insert(String::from("generator"), Value::U64(listing.header.generator as u64));
+    header.insert(String::from("bound"), Value::U64(listing.header.bound as u64));
+    header.insert(String::from("schema"), Value::U64(listing.header.schema as u64));
 
-        &Inst::TypeFloat { result_type, width } => new_instruction(
-            "OpTypeFloat",
-            Some(result_type.0),
-            Some(width.as_op())
-        ),
-        &Inst::TypeInt { result_type, width, signed } => new_instruction(
-            "OpTypeInt",
-            Some(result_type.0),
-            vec![
-                width.as_op(),
-                signed.as_op(),
-            ]
-        ),
-        &Inst::TypeVector { result_type, type_id, len } => new_instruction(
-            "OpTypeVector",
-            Some(result_type.0),
-            vec![
-                type_id.as_op(),
-                len.as_op(),
-            ]
-        ),
-        &Inst::TypeMatrix { result_type, type_id, cols } => new_instruction(
-            "OpTypeMatrix",
-            Some(result_type.0),
-            vec![
-                type_id.as_op(),
-                cols.as_op(),
-            ]
-        ),
-        &Inst::TypePointer { result_type, storage_class, pointee } => new_instruction(
-            "OpTypePointer",
-            Some(result_type.0),
-            vec![
-                storage_class.as_op(),
-                pointee.as_op(),
-            ]
-        ),
+    let mut result = Map::new();
+    result.insert(String::from("header"), Value::Object(header));
+    result.insert(String::from("bound"), Value::U64(listing.header.bound as u64));
+    result.insert(String::from("instructions"), Value::Array(instructions));
 
-        &Inst::Constant { result_type, result_id, ref val } => new_instruction(
-            "OpConstant",
-            Some(result_id.0),
-            vec![
-                result_type.as_op(),
-            ].into_iter()
-                .chain(
-                    match val.len() {
-                        1 => Some(
-                            Operand::LitFloat(unsafe {
-                                transmute(val[0])
-                            })
-                        ),
-                        2 => Some(
-                            Operand::LitDouble(unsafe {
-                                let mut a: [u32; 2] = Default::default();
-                                a.copy_from_slice(val);
-                                transmute(a)
-                            })
-                        ),
-                        _ => return Err(format!("Unexpected constant size {}", val.len()))
-                    }
-                )
-        ),
+    Value::Object(result)
+}
 
-        &Inst::Variable { result_type, result_id, storage_class, init } => new_instruction(
-            "OpVariable",
-            Some(result_id.0),
-            vec![
-                result_type.as_op(),
-                storage_class.as_op(),
-            ].into_iter()
-                .chain(
-                    if init.0 != 0 {
-                        Some(init.as_op())
-                    } else {
-                        None
-                    }
-                )
-        ),
-        &Inst::Load { result_type, result_id, value_id, memory_access } => new_instruction(
-            "OpLoad",
-            Some(result_id.0),
-            vec![
-                result_type.as_op(),
-                value_id.as_op(),
-                format!("{}", memory_access).as_str().as_op(),
-            ]
-        ),
-        &Inst::Store { ptr, obj, memory_access } => new_instruction(
-            "OpStore",
-            None,
-            vec![
-                ptr.as_op(),
-                obj.as_op(),
-                format!("{}", memory_access).as_str().as_op(),
-            ]
-        ),
+// Finite values are printed at their own width, so a float prints as the
+// shortest decimal that reads back to it, like spirv-dis does. NaN and
+// infinities use a hex float one past the largest exponent of the type.
+fn float_text(value: f64, text: String, max_exponent: u32) -> String {
+    if value.is_nan() {
+        format!("0x1.8p+{}", max_exponent)
+    } else if value.is_infinite() {
+        format!("{}0x1p+{}", if value > 0.0 { "" } else { "-" }, max_exponent)
+    } else {
+        text
+    }
+}
 
-        &Inst::CompositeExtract { result_type, result_id, obj, ref indices } => new_instruction(
-            "OpCompositeExtract",
-            Some(result_id.0),
-            vec![
-                result_type.as_op(),
-                obj.as_op(),
-            ].into_iter()
-                .chain(
-                    indices.into_iter()
-                        .map(|i| i.as_op())
-                )
-        ),
+fn operand_text(names: &Names, operand: &Operand) -> String {
+    match *operand {
+        Operand::Text(ref val) => val.clone(),
+        Operand::Id(val) | Operand::Type(val) => id_text(names, val),
+        Operand::Enum(ref val) | Operand::ExtInst(ref val) => val.clone(),
+        Operand::LitString(ref val) => format!("\"{}\"", val.replace('\\', "\\\\").replace('"', "\\\"")),
+        Operand::LitInt(val) => format!("{}", val),
+        Operand::LitSInt(val) => format!("{}", val),
+        Operand::LitLong(val) => format!("{}", val),
+        Operand::LitSLong(val) => format!("{}", val),
+        Operand::LitFloat(val) => float_text(val as f64, format!("{}", val), 128),
+        Operand::LitDouble(val) => float_text(val, format!("{}", val), 1024),
+    }
+}
 
-        &Inst::ExtInst { result_type, result_id, set, instruction, ref operands } => new_instruction(
-            "OpExtInst",
-            Some(result_id.0),
-            vec![
-                result_type.as_op(),
-                set.as_op(),
-                Operand::ExtInst(unsafe {
-                    transmute(instruction)
-                })
-            ].into_iter()
-                .chain(
-                    operands.iter().map(|id| id.as_op())
-                )
-        ),
-        &Inst::ExtInstImport { result_id, ref name } => new_instruction(
-            "OpExtInstImport",
-            Some(result_id.0),
-            Some(name.as_op())
-        ),
+// Width of the result id column, matching the default spirv-dis indentation
+const RESULT_COLUMN: usize = 15;
+
+fn instruction_text(names: &Names, inst: &Instruction) -> String {
+    let operands: Vec<_> = inst.operands.iter()
+        .map(|op| operand_text(names, op))
+        .collect();
+
+    if inst.is_comment() {
+        return format!("; {}", operands.join(" "));
+    }
 
-        _ => new_instruction(
-            inst.name(),
-            inst.defines_value_inner().map(|id| id.0),
-            inst.type_id_of().into_iter()
-                .map(|id| id.as_op())
-                .chain(
-                    inst.defines_type().into_iter()
-                        .map(|id| id.as_op())
-                )
-                .chain(
-                    inst.uses().into_iter()
-                        .map(|id| id.as_op())
-                )
-        )
+    let mut line = match inst.result_id {
+        Some(id) => format!("{:>width$} = ", id_text(names, id), width = RESULT_COLUMN - 3),
+        None => repeat(' ').take(RESULT_COLUMN).collect(),
+    };
+
+    line.push_str(&inst.class);
+    for operand in operands {
+        line.push(' ');
+        line.push_str(&operand);
     }
+
+    line
 }
 
-pub fn module_printer(module: Mod) -> Result<String, String> {
-    let operations = module.get_operations();
-    let mut instructions = Vec::with_capacity(operations.len() + 5);
-
-    instructions.extend(vec![
-        new_instruction(";", None, Some(
-            "SPIR-V".as_op()
-        ))?,
-        new_instruction(";", None, Some(
-            "Version: 1.0".as_op()
-        ))?,
-        new_instruction(";", None, Some(
-            "Generator: Rasen; 1".as_op()
-        ))?,
-        new_instruction(";", None, Some(
-            format!("Bound: {}", module.bound()).as_str().as_op()
-        ))?,
-        new_instruction(";", None, Some(
-            "Schema: 0".as_op()
-        ))?,
-    ].into_iter());
-
-    for op in operations {
-        instructions.push(convert_instruction(&op)?);
+pub fn to_spvasm_text(listing: &Listing) -> String {
+    let mut text = String::new();
+
+    for inst in header_comments(&listing.header).iter().chain(listing.instructions.iter()) {
+        text.push_str(&instruction_text(&listing.names, inst));
+        text.push('\n');
     }
 
-    let mut result = Map::new();
-    result.insert("bound", Value::U64(module.bound() as u64));
-    result.insert("instructions", Value::Array(instructions));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use embed::push_instruction;
+
+    use super::*;
+
+    fn module(body: &[(u32, &[u32])]) -> Vec<u32> {
+        let mut words = vec![MAGIC_NUMBER, 0x00010000, 0, 32, 0];
+        for &(opcode, operands) in body {
+            push_instruction(&mut words, opcode, operands);
+        }
+
+        words
+    }
+
+    fn constant_text(listing: &Listing, id: u32) -> String {
+        let inst = listing.instructions.iter()
+            .find(|inst| inst.class == "OpConstant" && inst.result_id == Some(id))
+            .unwrap();
+
+        operand_text(&listing.names, &inst.operands[1])
+    }
 
-    to_string(&result).map_err(|err| format!("{}", err))
+    #[test]
+    fn constants_follow_their_type() {
+        let words = module(&[
+            (21, &[1, 32, 1]),
+            (21, &[2, 32, 0]),
+            (22, &[3, 32]),
+            (22, &[4, 64]),
+            (21, &[5, 64, 1]),
+            (21, &[6, 64, 0]),
+            (43, &[1, 10, (-5i32) as u32]),
+            (43, &[2, 11, 4000000000]),
+            (43, &[3, 12, 0x3f000000]),
+            (43, &[4, 13, 0x00000000, 0xc0020000]),
+            (43, &[5, 14, 0x4d2fa200, 0xffffffff]),
+            (43, &[6, 15, 0x00000000, 0x00000001]),
+        ]);
+
+        let listing = decode_words(&words).unwrap();
+        assert_eq!(constant_text(&listing, 10), "-5");
+        assert_eq!(constant_text(&listing, 11), "4000000000");
+        assert_eq!(constant_text(&listing, 12), "0.5");
+        assert_eq!(constant_text(&listing, 13), "-2.25");
+        assert_eq!(constant_text(&listing, 14), "-3000000000");
+        assert_eq!(constant_text(&listing, 15), "4294967296");
+    }
+
+    #[test]
+    fn constant_sizes_are_checked() {
+        let words = module(&[
+            (22, &[1, 64]),
+            (43, &[1, 2, 0]),
+        ]);
+        assert_eq!(decode_words(&words).err().unwrap(), "Unexpected constant size 1 for type %1");
+
+        let words = module(&[
+            (43, &[1, 2, 0]),
+        ]);
+        assert_eq!(decode_words(&words).err().unwrap(), "Constant type %1 is not a scalar type");
+    }
+
+    #[test]
+    fn decorations_print_their_operands() {
+        let words = module(&[
+            (71, &[1, 11, 0]),
+            (71, &[2, 33, 3]),
+            (71, &[2, 34, 1]),
+            (71, &[4, 14]),
+            (72, &[5, 0, 11, 1]),
+            (72, &[5, 1, 35, 16]),
+        ]);
+
+        let listing = decode_words(&words).unwrap();
+        let lines: Vec<_> = listing.instructions.iter()
+            .map(|inst| instruction_text(&listing.names, inst).trim().to_string())
+            .collect();
+
+        assert_eq!(lines, vec![
+            "OpDecorate %1 BuiltIn Position",
+            "OpDecorate %2 Binding 3",
+            "OpDecorate %2 DescriptorSet 1",
+            "OpDecorate %4 Flat",
+            "OpMemberDecorate %5 0 BuiltIn PointSize",
+            "OpMemberDecorate %5 1 Offset 16",
+        ]);
+    }
+
+    #[test]
+    fn floats_print_at_their_width() {
+        let names = Names::new();
+
+        assert_eq!(operand_text(&names, &Operand::LitFloat(0.1)), "0.1");
+        assert_eq!(operand_text(&names, &Operand::LitDouble(0.1)), "0.1");
+
+        assert_eq!(operand_text(&names, &Operand::LitFloat(::std::f32::NAN)), "0x1.8p+128");
+        assert_eq!(operand_text(&names, &Operand::LitFloat(::std::f32::NEG_INFINITY)), "-0x1p+128");
+        assert_eq!(operand_text(&names, &Operand::LitDouble(::std::f64::NAN)), "0x1.8p+1024");
+        assert_eq!(operand_text(&names, &Operand::LitDouble(::std::f64::INFINITY)), "0x1p+1024");
+
+        let value = new_operand(&names, &Operand::LitFloat(0.1));
+        assert_eq!(value.find("value").and_then(Value::as_f64), Some(0.1));
+    }
+
+    #[test]
+    fn modules_are_read_in_either_byte_order() {
+        let little = [0x03, 0x02, 0x23, 0x07, 0x00, 0x00, 0x01, 0x00];
+        let big = [0x07, 0x23, 0x02, 0x03, 0x00, 0x01, 0x00, 0x00];
+
+        assert_eq!(words_from_bytes(&little).unwrap(), vec![MAGIC_NUMBER, 0x00010000]);
+        assert_eq!(words_from_bytes(&big).unwrap(), vec![MAGIC_NUMBER, 0x00010000]);
+
+        assert_eq!(words_from_bytes(&little[..6]).err().unwrap(), "SPIR-V module length 6 is not a multiple of 4");
+        assert_eq!(words_from_bytes(&[0; 8]).err().unwrap(), "Input is not a SPIR-V module");
+    }
+
+    #[test]
+    fn malformed_instructions_are_rejected() {
+        let mut words = module(&[(17, &[1])]);
+        words.push((3 << 16) | 17);
+        assert_eq!(decode_words(&words).err().unwrap(), "Invalid word count 3 at word 7");
+
+        let mut words = module(&[(17, &[1])]);
+        words.push(17);
+        assert_eq!(decode_words(&words).err().unwrap(), "Invalid word count 0 at word 7");
+
+        let words = module(&[(17, &[1, 2])]);
+        assert_eq!(decode_words(&words).err().unwrap(), "OpCapability has 1 unexpected trailing words");
+
+        assert_eq!(decode_words(&[MAGIC_NUMBER, 0, 0]).err().unwrap(), "SPIR-V module is too short");
+    }
 }
@@ -0,0 +1,394 @@
+use std::collections::BTreeMap;
+
+use rasen::ShaderType;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+use serde_json::{from_str, from_value, Error as JsonError, Value, Map};
+
+use diagnostic::*;
+use migrate::*;
+
+/// Shader stage a graph is compiled for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stage {
+    /// Vertex shader
+    #[serde(rename = "vertex")]
+    Vertex,
+    /// Fragment shader
+    #[serde(rename = "fragment")]
+    Fragment,
+}
+
+impl Stage {
+    /// Matching rasen shader type
+    pub fn shader_type(&self) -> ShaderType {
+        match *self {
+            Stage::Vertex => ShaderType::Vertex,
+            Stage::Fragment => ShaderType::Fragment,
+        }
+    }
+}
+
+impl Default for Stage {
+    fn default() -> Stage {
+        Stage::Fragment
+    }
+}
+
+/// Properties of a node, depending on its title
+#[derive(Debug, Clone)]
+pub enum NodeData {
+    /// Stage input read from a location
+    Input {
+        /// Interface location
+        location: u32,
+        /// Name of the input type
+        data_type: String,
+    },
+    /// Stage output written to a location
+    Output {
+        /// Interface location
+        location: u32,
+        /// Name of the output type
+        data_type: String,
+    },
+    /// Composite value built from the node inputs
+    Construct {
+        /// Name of the constructed type
+        data_type: String,
+    },
+    /// Component of a vector or column of a matrix
+    Extract {
+        /// Index of the component
+        index: u32,
+    },
+    /// The shape of the value depends on the type of the constant, it is
+    /// checked when the graph is built
+    Constant {
+        /// Name of the constant type
+        data_type: String,
+        /// Value as a number, boolean, or array of them
+        value: Value,
+    },
+    /// Operations only carry their title
+    Operation(String),
+}
+
+impl NodeData {
+    /// Title of the node in the editor
+    pub fn title(&self) -> &str {
+        match *self {
+            NodeData::Input { .. } => "Input",
+            NodeData::Output { .. } => "Output",
+            NodeData::Construct { .. } => "Construct",
+            NodeData::Extract { .. } => "Extract",
+            NodeData::Constant { .. } => "Constant",
+            NodeData::Operation(ref title) => title.as_str(),
+        }
+    }
+}
+
+/// A node of the graph document
+#[derive(Debug, Clone)]
+pub struct NodeDocument {
+    /// Properties of the node
+    pub data: NodeData,
+    /// Fields this kind of node does not use, written back untouched
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct NodeTitle {
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct InterfaceFields {
+    location: u32,
+    #[serde(rename = "type")]
+    data_type: String,
+}
+
+#[derive(Deserialize)]
+struct ConstructFields {
+    #[serde(rename = "type")]
+    data_type: String,
+}
+
+#[derive(Deserialize)]
+struct ExtractFields {
+    index: u32,
+}
+
+#[derive(Deserialize)]
+struct ConstantFields {
+    #[serde(rename = "type")]
+    data_type: String,
+    value: Value,
+}
+
+// Fields read by each kind of node, anything else is kept as extra
+const INTERFACE_KEYS: &'static [&'static str] = &["title", "location", "type"];
+const CONSTRUCT_KEYS: &'static [&'static str] = &["title", "type"];
+const EXTRACT_KEYS: &'static [&'static str] = &["title", "index"];
+const CONSTANT_KEYS: &'static [&'static str] = &["title", "type", "value"];
+const OPERATION_KEYS: &'static [&'static str] = &["title"];
+
+// Messages of values deserialized in memory have no meaningful position
+fn value_error(context: &str, err: JsonError) -> String {
+    match err {
+        JsonError::Syntax(code, _, _) => format!("{}: {}", context, code),
+        err => format!("{}: {}", context, err),
+    }
+}
+
+fn read_value<T>(value: Value, context: &str) -> Result<T, String> where T: Deserialize {
+    from_value(value).map_err(|err| value_error(context, err))
+}
+
+impl NodeDocument {
+    /// Read a node from the fields of its JSON object
+    pub fn from_fields(fields: Map<String, Value>) -> Result<NodeDocument, String> {
+        let object = Value::Object(fields);
+        let title: NodeTitle = read_value(object.clone(), "node")?;
+
+        let (data, known) = match title.title.as_str() {
+            "Input" | "Output" => {
+                let fields: InterfaceFields = read_value(object.clone(), "interface")?;
+                let data = if title.title == "Input" {
+                    NodeData::Input { location: fields.location, data_type: fields.data_type }
+                } else {
+                    NodeData::Output { location: fields.location, data_type: fields.data_type }
+                };
+
+                (data, INTERFACE_KEYS)
+            },
+            "Construct" => {
+                let fields: ConstructFields = read_value(object.clone(), "construct")?;
+                (NodeData::Construct { data_type: fields.data_type }, CONSTRUCT_KEYS)
+            },
+            "Extract" => {
+                let fields: ExtractFields = read_value(object.clone(), "extract")?;
+                (NodeData::Extract { index: fields.index }, EXTRACT_KEYS)
+            },
+            "Constant" => {
+                let fields: ConstantFields = read_value(object.clone(), "constant")?;
+                (NodeData::Constant { data_type: fields.data_type, value: fields.value }, CONSTANT_KEYS)
+            },
+            _ => (NodeData::Operation(title.title), OPERATION_KEYS),
+        };
+
+        let extra = match object {
+            Value::Object(fields) => fields.into_iter()
+                .filter(|&(ref key, _)| !known.contains(&key.as_str()))
+                .collect(),
+            _ => Map::new(),
+        };
+
+        Ok(NodeDocument {
+            data: data,
+            extra: extra,
+        })
+    }
+
+    /// Fields of the JSON object of the node
+    pub fn to_fields(&self) -> Map<String, Value> {
+        let mut fields = self.extra.clone();
+        fields.insert(String::from("title"), Value::String(String::from(self.data.title())));
+
+        match self.data {
+            NodeData::Input { location, ref data_type } |
+            NodeData::Output { location, ref data_type } => {
+                fields.insert(String::from("location"), Value::U64(location as u64));
+                fields.insert(String::from("type"), Value::String(data_type.clone()));
+            },
+            NodeData::Construct { ref data_type } => {
+                fields.insert(String::from("type"), Value::String(data_type.clone()));
+            },
+            NodeData::Extract { index } => {
+                fields.insert(String::from("index"), Value::U64(index as u64));
+            },
+            NodeData::Constant { ref data_type, ref value } => {
+                fields.insert(String::from("type"), Value::String(data_type.clone()));
+                fields.insert(String::from("value"), value.clone());
+            },
+            NodeData::Operation(_) => {},
+        }
+
+        fields
+    }
+}
+
+impl Serialize for NodeDocument {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error> where S: Serializer {
+        self.to_fields().serialize(serializer)
+    }
+}
+
+impl Deserialize for NodeDocument {
+    fn deserialize<D>(deserializer: &mut D) -> Result<NodeDocument, D::Error> where D: Deserializer {
+        let fields: Map<String, Value> = Deserialize::deserialize(deserializer)?;
+        NodeDocument::from_fields(fields).map_err(D::Error::custom)
+    }
+}
+
+/// An edge of the graph document, connecting the output of the `from` node
+/// to the `input` pin of the `to` node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeDocument {
+    /// Key of the node the value comes from
+    pub from: u64,
+    /// Key of the node the value goes to
+    pub to: u64,
+    /// Input of the destination node
+    pub input: u32,
+}
+
+/// A graph as saved by the editor, deserializing it upgrades documents
+/// written by older versions
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphDocument {
+    /// Version of the document format, see `CURRENT_VERSION`
+    pub version: u64,
+    /// Stage the graph is compiled for
+    pub stage: Stage,
+    /// Nodes by key
+    pub nodes: BTreeMap<String, NodeDocument>,
+    /// Edges between the nodes
+    pub edges: Vec<EdgeDocument>,
+}
+
+// The top level of a document, with its nodes and edges left to be read one
+// by one so that a bad node doesn't hide the others
+#[derive(Deserialize)]
+struct DocumentFields {
+    #[serde(default)]
+    stage: Stage,
+    nodes: Map<String, Value>,
+    edges: Vec<Value>,
+}
+
+fn read_fields(data: Value) -> Result<DocumentFields, String> {
+    let mut data = match data {
+        Value::Object(data) => data,
+        _ => return Err(String::from("JSON input is not an object")),
+    };
+
+    upgrade(&mut data)?;
+    read_value(Value::Object(data), "data")
+}
+
+// Read the nodes and edges one by one, leaving out the ones that can't be
+// read and reporting them instead
+fn read_graph(fields: DocumentFields) -> (GraphDocument, Vec<Diagnostic>) {
+    let mut document = GraphDocument {
+        version: CURRENT_VERSION,
+        stage: fields.stage,
+        nodes: BTreeMap::new(),
+        edges: Vec::new(),
+    };
+
+    let mut diagnostics = Vec::new();
+
+    for (key, node) in fields.nodes {
+        let node = read_value(node, "node").and_then(NodeDocument::from_fields);
+        match node {
+            Ok(node) => {
+                for field in node.extra.keys() {
+                    diagnostics.push(
+                        Diagnostic::warning(format!("unknown field {:?} on {} node", field, node.data.title()))
+                            .at_node(key.as_str())
+                    );
+                }
+
+                document.nodes.insert(key, node);
+            },
+            Err(msg) => diagnostics.push(Diagnostic::from(msg).at_node(key.as_str())),
+        }
+    }
+
+    for (index, edge) in fields.edges.into_iter().enumerate() {
+        match read_value(edge, "edge") {
+            Ok(edge) => document.edges.push(edge),
+            Err(msg) => diagnostics.push(Diagnostic::from(msg).at_edge(index)),
+        }
+    }
+
+    (document, diagnostics)
+}
+
+impl Deserialize for GraphDocument {
+    fn deserialize<D>(deserializer: &mut D) -> Result<GraphDocument, D::Error> where D: Deserializer {
+        let data: Value = Deserialize::deserialize(deserializer)?;
+        let fields = read_fields(data).map_err(D::Error::custom)?;
+
+        let (document, diagnostics) = read_graph(fields);
+        match diagnostics.into_iter().find(|diag| diag.severity == Severity::Error) {
+            Some(diag) => Err(D::Error::custom(diag.message)),
+            None => Ok(document),
+        }
+    }
+}
+
+/// Read a graph document, upgrading it from older versions. The nodes and
+/// edges that can't be read are reported as errors and left out of the
+/// document, along with warnings for the node fields that will be ignored.
+pub fn read_document(input: &str) -> Result<(GraphDocument, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let data: Value = match from_str(input) {
+        Ok(val) => val,
+        Err(err) => return Err(vec![
+            Diagnostic::error(format!("JSON error: {:?}", err))
+        ])
+    };
+
+    let fields = read_fields(data).map_err(|msg| vec![Diagnostic::from(msg)])?;
+    Ok(read_graph(fields))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::from_str;
+
+    use super::{read_document, GraphDocument, NodeData, Stage};
+
+    #[test]
+    fn deserializing_upgrades_documents() {
+        let input = r#"{
+            "nodes": { "0": { "title": "Input", "location": 2, "type": "vec4", "x": 1 } },
+            "edges": []
+        }"#;
+
+        let document: GraphDocument = from_str(input).unwrap();
+        assert_eq!(document.version, 1);
+        assert_eq!(document.stage, Stage::Fragment);
+
+        let node = &document.nodes["0"];
+        assert!(node.extra.contains_key("x"));
+        match node.data {
+            NodeData::Input { location, ref data_type } => {
+                assert_eq!(location, 2);
+                assert_eq!(data_type, "vec4");
+            },
+            ref data => panic!("{:?}", data),
+        }
+
+        let (_, diagnostics) = read_document(input).unwrap();
+        let messages: Vec<_> = diagnostics.into_iter().map(|diag| diag.message).collect();
+        assert_eq!(messages, vec![String::from("unknown field \"x\" on Input node")]);
+    }
+
+    #[test]
+    fn deserializing_rejects_unreadable_nodes() {
+        let input = r#"{
+            "nodes": { "0": { "title": "Input", "type": "vec4" } },
+            "edges": []
+        }"#;
+
+        let err = from_str::<GraphDocument>(input).unwrap_err();
+        assert!(format!("{}", err).contains("interface: missing field \"location\""), "{}", err);
+
+        let (document, diagnostics) = read_document(input).unwrap();
+        assert!(document.nodes.is_empty());
+        assert_eq!(diagnostics[0].node, Some(String::from("0")));
+    }
+}
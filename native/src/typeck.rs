@@ -0,0 +1,611 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use rasen::*;
+
+use diagnostic::*;
+use parser::Edge;
+
+/// Component type of the values of a graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scalar {
+    /// `bool`
+    Bool,
+    /// 32-bit signed `int`
+    Int,
+    /// 32-bit unsigned `uint`
+    UInt,
+    /// 32-bit `float`
+    Float,
+    /// 64-bit `double`
+    Double,
+}
+
+impl Scalar {
+    /// Name of the scalar type in the editor
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Scalar::Bool => "bool",
+            Scalar::Int => "int",
+            Scalar::UInt => "uint",
+            Scalar::Float => "float",
+            Scalar::Double => "double",
+        }
+    }
+
+    fn prefix(&self) -> &'static str {
+        match *self {
+            Scalar::Bool => "b",
+            Scalar::Int => "i",
+            Scalar::UInt => "u",
+            Scalar::Float => "",
+            Scalar::Double => "d",
+        }
+    }
+
+    fn is_float(&self) -> bool {
+        match *self {
+            Scalar::Float | Scalar::Double => true,
+            _ => false,
+        }
+    }
+}
+
+/// The type of a value flowing along an edge, displayed the same way the
+/// editor names constant and interface types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    /// A single component
+    Scalar(Scalar),
+    /// A vector with the given number of components
+    Vector(u32, Scalar),
+    /// A square matrix with the given number of columns
+    Matrix(u32, Scalar),
+}
+
+impl Type {
+    /// Type of a rasen type name, `None` for the types graphs can't hold
+    pub fn from_typename(ty: &TypeName) -> Option<Type> {
+        let scalar = |ty: &TypeName| match Type::from_typename(ty) {
+            Some(Type::Scalar(scalar)) => Some(scalar),
+            _ => None,
+        };
+
+        match *ty {
+            TypeName::Bool => Some(Type::Scalar(Scalar::Bool)),
+            TypeName::Int(true) => Some(Type::Scalar(Scalar::Int)),
+            TypeName::Int(false) => Some(Type::Scalar(Scalar::UInt)),
+            TypeName::Float(false) => Some(Type::Scalar(Scalar::Float)),
+            TypeName::Float(true) => Some(Type::Scalar(Scalar::Double)),
+            TypeName::Vec(size, component) => scalar(component).map(|s| Type::Vector(size, s)),
+            TypeName::Mat(size, column) => scalar(column).map(|s| Type::Matrix(size, s)),
+            _ => None,
+        }
+    }
+
+    /// Type of a rasen constant
+    pub fn from_value(value: &TypedValue) -> Option<Type> {
+        Some(match *value {
+            TypedValue::Bool(_) => Type::Scalar(Scalar::Bool),
+            TypedValue::Int(_) => Type::Scalar(Scalar::Int),
+            TypedValue::UInt(_) => Type::Scalar(Scalar::UInt),
+            TypedValue::Float(_) => Type::Scalar(Scalar::Float),
+            TypedValue::Double(_) => Type::Scalar(Scalar::Double),
+
+            TypedValue::BVec2(..) => Type::Vector(2, Scalar::Bool),
+            TypedValue::BVec3(..) => Type::Vector(3, Scalar::Bool),
+            TypedValue::BVec4(..) => Type::Vector(4, Scalar::Bool),
+            TypedValue::IVec2(..) => Type::Vector(2, Scalar::Int),
+            TypedValue::IVec3(..) => Type::Vector(3, Scalar::Int),
+            TypedValue::IVec4(..) => Type::Vector(4, Scalar::Int),
+            TypedValue::UVec2(..) => Type::Vector(2, Scalar::UInt),
+            TypedValue::UVec3(..) => Type::Vector(3, Scalar::UInt),
+            TypedValue::UVec4(..) => Type::Vector(4, Scalar::UInt),
+            TypedValue::Vec2(..) => Type::Vector(2, Scalar::Float),
+            TypedValue::Vec3(..) => Type::Vector(3, Scalar::Float),
+            TypedValue::Vec4(..) => Type::Vector(4, Scalar::Float),
+            TypedValue::DVec2(..) => Type::Vector(2, Scalar::Double),
+            TypedValue::DVec3(..) => Type::Vector(3, Scalar::Double),
+            TypedValue::DVec4(..) => Type::Vector(4, Scalar::Double),
+
+            TypedValue::Mat2(_) => Type::Matrix(2, Scalar::Float),
+            TypedValue::Mat3(_) => Type::Matrix(3, Scalar::Float),
+            TypedValue::Mat4(_) => Type::Matrix(4, Scalar::Float),
+            TypedValue::DMat2(_) => Type::Matrix(2, Scalar::Double),
+            TypedValue::DMat3(_) => Type::Matrix(3, Scalar::Double),
+            TypedValue::DMat4(_) => Type::Matrix(4, Scalar::Double),
+
+            _ => return None,
+        })
+    }
+
+    /// Type of the components
+    pub fn scalar(&self) -> Scalar {
+        match *self {
+            Type::Scalar(scalar) | Type::Vector(_, scalar) | Type::Matrix(_, scalar) => scalar,
+        }
+    }
+
+    /// Total number of scalar components
+    pub fn components(&self) -> u32 {
+        match *self {
+            Type::Scalar(_) => 1,
+            Type::Vector(size, _) => size,
+            Type::Matrix(size, _) => size * size,
+        }
+    }
+
+    fn is_matrix(&self) -> bool {
+        match *self {
+            Type::Matrix(..) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Type::Scalar(scalar) => write!(f, "{}", scalar.name()),
+            Type::Vector(size, scalar) => write!(f, "{}vec{}", scalar.prefix(), size),
+            Type::Matrix(size, scalar) => write!(f, "{}mat{}", scalar.prefix(), size),
+        }
+    }
+}
+
+/// What the type checker needs to know about a node, kept aside since the
+/// nodes themselves are moved into the rasen graph
+#[derive(Debug, Clone, Copy)]
+pub enum Kind {
+    /// Input at a location, with its declared type
+    Input(u32, Option<Type>),
+    /// Output at a location, with its declared type
+    Output(u32, Option<Type>),
+    /// Constant of the given type
+    Constant(Option<Type>),
+    /// Composite built from its inputs
+    Construct(Option<Type>),
+    /// Component of a vector or column of a matrix at an index
+    Extract(u32),
+    /// Operation with the given node title
+    Operation(&'static str),
+    /// Node the type checker doesn't know about
+    Unknown,
+}
+
+impl Kind {
+    /// Kind of a rasen node
+    pub fn from_node(node: &Node) -> Kind {
+        match *node {
+            Node::Input(location, ty) => Kind::Input(location, Type::from_typename(ty)),
+            Node::Output(location, ty) => Kind::Output(location, Type::from_typename(ty)),
+            Node::Constant(ref value) => Kind::Constant(Type::from_value(value)),
+            Node::Construct(ty) => Kind::Construct(Type::from_typename(ty)),
+            Node::Extract(index) => Kind::Extract(index),
+
+            Node::Add => Kind::Operation("Add"),
+            Node::Substract => Kind::Operation("Substract"),
+            Node::Multiply => Kind::Operation("Multiply"),
+            Node::Divide => Kind::Operation("Divide"),
+            Node::Modulus => Kind::Operation("Modulus"),
+            Node::Dot => Kind::Operation("Dot"),
+            Node::Normalize => Kind::Operation("Normalize"),
+            Node::Clamp => Kind::Operation("Clamp"),
+            Node::Mix => Kind::Operation("Mix"),
+            Node::Cross => Kind::Operation("Cross"),
+            Node::Floor => Kind::Operation("Floor"),
+            Node::Ceil => Kind::Operation("Ceil"),
+            Node::Round => Kind::Operation("Round"),
+            Node::Sin => Kind::Operation("Sin"),
+            Node::Cos => Kind::Operation("Cos"),
+            Node::Tan => Kind::Operation("Tan"),
+            Node::Pow => Kind::Operation("Pow"),
+            Node::Min => Kind::Operation("Min"),
+            Node::Max => Kind::Operation("Max"),
+            Node::Length => Kind::Operation("Length"),
+            Node::Distance => Kind::Operation("Distance"),
+            Node::Reflect => Kind::Operation("Reflect"),
+            Node::Refract => Kind::Operation("Refract"),
+
+            _ => Kind::Unknown,
+        }
+    }
+
+    /// Number of inputs the node reads
+    pub fn arity(&self) -> u32 {
+        match *self {
+            Kind::Input(..) | Kind::Constant(_) | Kind::Unknown => 0,
+            Kind::Output(..) | Kind::Extract(_) => 1,
+            Kind::Construct(ty) => match ty {
+                Some(Type::Matrix(size, _)) => size,
+                Some(ty) => ty.components(),
+                None => 0,
+            },
+
+            Kind::Operation(title) => match title {
+                "Normalize" | "Floor" | "Ceil" | "Round" |
+                "Sin" | "Cos" | "Tan" | "Length" => 1,
+                "Clamp" | "Mix" | "Refract" => 3,
+                _ => 2,
+            },
+        }
+    }
+}
+
+struct TypeError {
+    pin: Option<u32>,
+    message: String,
+}
+
+fn node_error<S: Into<String>>(message: S) -> TypeError {
+    TypeError {
+        pin: None,
+        message: message.into(),
+    }
+}
+
+fn mismatch<S: fmt::Display>(pin: u32, expected: S, found: Type) -> TypeError {
+    TypeError {
+        pin: Some(pin),
+        message: format!("expected {}, found {}", expected, found),
+    }
+}
+
+fn expect_numeric(pin: u32, ty: Type) -> Result<(), TypeError> {
+    if ty.scalar() == Scalar::Bool {
+        return Err(mismatch(pin, "a numeric type", ty));
+    }
+
+    Ok(())
+}
+
+fn expect_float(pin: u32, ty: Type, double: bool) -> Result<(), TypeError> {
+    match ty {
+        Type::Scalar(scalar) | Type::Vector(_, scalar) if scalar == Scalar::Float || (double && scalar == Scalar::Double) => Ok(()),
+        _ if double => Err(mismatch(pin, "a floating-point scalar or vector", ty)),
+        _ => Err(mismatch(pin, "a float scalar or vector", ty)),
+    }
+}
+
+fn expect_same(pin: u32, expected: Type, found: Type) -> Result<(), TypeError> {
+    if found != expected {
+        return Err(mismatch(pin, expected, found));
+    }
+
+    Ok(())
+}
+
+// Accept either the type of the first operand or its scalar type
+fn expect_same_or_scalar(pin: u32, expected: Type, found: Type) -> Result<(), TypeError> {
+    if found != expected && found != Type::Scalar(expected.scalar()) {
+        return Err(mismatch(pin, format!("{} or {}", expected, expected.scalar().name()), found));
+    }
+
+    Ok(())
+}
+
+fn arithmetic(title: &str, a: Type, b: Type) -> Result<Type, TypeError> {
+    expect_numeric(0, a)?;
+    expect_numeric(1, b)?;
+
+    if title == "Modulus" && a.is_matrix() {
+        return Err(mismatch(0, "a scalar or vector", a));
+    }
+    if a.scalar() != b.scalar() {
+        return Err(mismatch(1, format!("a {} type", a.scalar().name()), b));
+    }
+
+    match (a, b) {
+        _ if a == b => Ok(a),
+
+        (Type::Vector(..), Type::Scalar(_)) |
+        (Type::Matrix(..), Type::Scalar(_)) => Ok(a),
+        (Type::Scalar(_), Type::Vector(..)) |
+        (Type::Scalar(_), Type::Matrix(..)) => Ok(b),
+
+        (Type::Matrix(n, _), Type::Vector(m, _)) |
+        (Type::Vector(m, _), Type::Matrix(n, _)) if title == "Multiply" && n == m => {
+            Ok(Type::Vector(n, a.scalar()))
+        },
+
+        _ => Err(mismatch(1, a, b)),
+    }
+}
+
+fn operation(title: &str, args: &[Type]) -> Result<Type, TypeError> {
+    let a = args[0];
+
+    match title {
+        "Add" | "Substract" | "Multiply" | "Divide" | "Modulus" => arithmetic(title, a, args[1]),
+
+        "Normalize" | "Floor" | "Ceil" | "Round" => {
+            expect_float(0, a, true)?;
+            Ok(a)
+        },
+        "Sin" | "Cos" | "Tan" => {
+            expect_float(0, a, false)?;
+            Ok(a)
+        },
+        "Pow" => {
+            expect_float(0, a, false)?;
+            expect_same(1, a, args[1])?;
+            Ok(a)
+        },
+        "Length" => {
+            expect_float(0, a, true)?;
+            Ok(Type::Scalar(a.scalar()))
+        },
+        "Distance" | "Dot" => {
+            expect_float(0, a, true)?;
+            expect_same(1, a, args[1])?;
+            Ok(Type::Scalar(a.scalar()))
+        },
+        "Cross" => {
+            match a {
+                Type::Vector(3, scalar) if scalar.is_float() => {},
+                _ => return Err(mismatch(0, "a 3 component floating-point vector", a)),
+            }
+
+            expect_same(1, a, args[1])?;
+            Ok(a)
+        },
+        "Reflect" => {
+            expect_float(0, a, true)?;
+            expect_same(1, a, args[1])?;
+            Ok(a)
+        },
+        "Refract" => {
+            expect_float(0, a, true)?;
+            expect_same(1, a, args[1])?;
+            expect_same(2, Type::Scalar(a.scalar()), args[2])?;
+            Ok(a)
+        },
+        "Min" | "Max" => {
+            expect_numeric(0, a)?;
+            if a.is_matrix() {
+                return Err(mismatch(0, "a scalar or vector", a));
+            }
+
+            expect_same_or_scalar(1, a, args[1])?;
+            Ok(a)
+        },
+        "Clamp" => {
+            expect_numeric(0, a)?;
+            if a.is_matrix() {
+                return Err(mismatch(0, "a scalar or vector", a));
+            }
+
+            expect_same_or_scalar(1, a, args[1])?;
+            expect_same_or_scalar(2, a, args[2])?;
+            Ok(a)
+        },
+        "Mix" => {
+            expect_float(0, a, true)?;
+            expect_same(1, a, args[1])?;
+            expect_same_or_scalar(2, a, args[2])?;
+            Ok(a)
+        },
+
+        _ => Err(node_error(format!("Unknown operation {}", title))),
+    }
+}
+
+fn construct(ty: Type, args: &[Type]) -> Result<(), TypeError> {
+    match ty {
+        Type::Scalar(_) => Err(node_error(format!("Cannot construct a scalar {}", ty))),
+
+        Type::Vector(size, scalar) => {
+            let mut count = 0;
+            for (pin, arg) in args.iter().enumerate() {
+                match *arg {
+                    Type::Scalar(s) | Type::Vector(_, s) if s == scalar => count += arg.components(),
+                    _ => return Err(mismatch(pin as u32, format!("a {} scalar or vector", scalar.name()), *arg)),
+                }
+            }
+
+            if count != size {
+                return Err(node_error(format!("{} needs {} components, got {}", ty, size, count)));
+            }
+
+            Ok(())
+        },
+
+        Type::Matrix(size, scalar) => {
+            let column = Type::Vector(size, scalar);
+            for (pin, arg) in args.iter().enumerate() {
+                expect_same(pin as u32, column, *arg)?;
+            }
+
+            if args.len() != size as usize {
+                return Err(node_error(format!("{} needs {} columns, got {}", ty, size, args.len())));
+            }
+
+            Ok(())
+        },
+    }
+}
+
+fn extract(index: u32, ty: Type) -> Result<Type, TypeError> {
+    let (size, res) = match ty {
+        Type::Vector(size, scalar) => (size, Type::Scalar(scalar)),
+        Type::Matrix(size, scalar) => (size, Type::Vector(size, scalar)),
+        Type::Scalar(_) => return Err(mismatch(0, "a vector or matrix", ty)),
+    };
+
+    if index >= size {
+        return Err(node_error(format!("Index {} is out of bounds for {}", index, ty)));
+    }
+
+    Ok(res)
+}
+
+// Output type of a node that does not depend on its inputs
+fn declared(kind: &Kind) -> Option<Type> {
+    match *kind {
+        Kind::Input(_, ty) | Kind::Constant(ty) | Kind::Construct(ty) => ty,
+        _ => None,
+    }
+}
+
+// Compute the output type of a node from the types of its connected inputs,
+// missing inputs are left to the structural validation
+fn infer(kind: &Kind, args: &[Option<Type>]) -> Result<Option<Type>, TypeError> {
+    if let Kind::Construct(Some(ty)) = *kind {
+        let args: Vec<_> = args.iter()
+            .cloned()
+            .filter_map(|arg| arg)
+            .collect();
+
+        construct(ty, &args)?;
+    }
+
+    if args.iter().any(Option::is_none) {
+        return Ok(declared(kind));
+    }
+
+    let args: Vec<_> = args.iter()
+        .cloned()
+        .filter_map(|arg| arg)
+        .collect();
+
+    match *kind {
+        Kind::Output(_, Some(ty)) => {
+            expect_same(0, ty, args[0])?;
+            Ok(None)
+        },
+        Kind::Extract(index) => extract(index, args[0]).map(Some),
+        Kind::Operation(title) => operation(title, &args).map(Some),
+        _ => Ok(declared(kind)),
+    }
+}
+
+// Type expected at each input of a node: the connected type, or for an
+// unconnected input the type implied by the declaration or the other inputs
+fn expected_inputs(kind: &Kind, args: &[Option<Type>]) -> Vec<Option<Type>> {
+    let first = args.iter().cloned().filter_map(|arg| arg).next();
+
+    args.iter()
+        .enumerate()
+        .map(|(pin, arg)| arg.or(match *kind {
+            Kind::Output(_, ty) => ty,
+            Kind::Construct(Some(Type::Matrix(size, scalar))) => Some(Type::Vector(size, scalar)),
+            Kind::Operation("Refract") if pin == 2 => first.map(|ty| Type::Scalar(ty.scalar())),
+            Kind::Operation(_) => first,
+            _ => None,
+        }))
+        .collect()
+}
+
+/// Types inferred for a node, `None` where they are unknown
+#[derive(Debug, Clone)]
+pub struct NodeTypes {
+    /// Type of the value the node produces
+    pub output: Option<Type>,
+    /// Type expected at each input of the node
+    pub inputs: Vec<Option<Type>>,
+}
+
+struct Inference<'a> {
+    nodes: &'a BTreeMap<String, Kind>,
+    inputs: HashMap<&'a str, BTreeMap<u32, &'a Edge>>,
+    types: BTreeMap<String, NodeTypes>,
+    visiting: HashSet<&'a str>,
+    errors: Vec<Diagnostic>,
+}
+
+impl<'a> Inference<'a> {
+    fn output(&mut self, key: &'a str) -> Option<Type> {
+        if let Some(types) = self.types.get(key) {
+            return types.output;
+        }
+
+        // Cycles are left to the structural validation
+        if !self.visiting.insert(key) {
+            return None;
+        }
+
+        let kind = match self.nodes.get(key) {
+            Some(kind) => *kind,
+            None => return None,
+        };
+
+        let edges: Vec<_> = match self.inputs.get(key) {
+            Some(edges) => edges.iter().map(|(pin, edge)| (*pin, *edge)).collect(),
+            None => Vec::new(),
+        };
+
+        let mut args = vec![None; kind.arity() as usize];
+        let mut unknown = false;
+        for (pin, edge) in edges {
+            let ty = self.output(&edge.from);
+            unknown |= ty.is_none();
+
+            if let Some(arg) = args.get_mut(pin as usize) {
+                *arg = ty;
+            }
+        }
+
+        // Inputs of unknown type have been reported somewhere upstream already
+        let output = if unknown { Ok(declared(&kind)) } else { infer(&kind, &args) };
+        let output = match output {
+            Ok(ty) => ty,
+            Err(err) => {
+                let mut diag = Diagnostic::error(err.message).at_node(key);
+                if let Some(pin) = err.pin {
+                    diag = diag.at_pin(pin);
+
+                    let edge = self.inputs.get(key).and_then(|edges| edges.get(&pin));
+                    if let Some(edge) = edge {
+                        diag = diag.at_edge(edge.index);
+                    }
+                }
+
+                self.errors.push(diag);
+                None
+            },
+        };
+
+        self.types.insert(String::from(key), NodeTypes {
+            output: output,
+            inputs: expected_inputs(&kind, &args),
+        });
+
+        output
+    }
+}
+
+/// Infer the type of every node the graph allows, checking each edge against
+/// the inputs accepted by its destination. Nodes downstream of an error keep
+/// the types that can still be deduced from their declaration.
+pub fn infer_types(nodes: &BTreeMap<String, Kind>, edges: &[Edge]) -> (BTreeMap<String, NodeTypes>, Vec<Diagnostic>) {
+    let mut inputs = HashMap::new();
+    for edge in edges {
+        inputs.entry(edge.to.as_str())
+            .or_insert_with(BTreeMap::new)
+            .insert(edge.input, edge);
+    }
+
+    let mut inference = Inference {
+        nodes: nodes,
+        inputs: inputs,
+        types: BTreeMap::new(),
+        visiting: HashSet::new(),
+        errors: Vec::new(),
+    };
+
+    for key in nodes.keys() {
+        inference.output(key);
+    }
+
+    (inference.types, inference.errors)
+}
+
+/// Infer the type of every node in the graph, failing on any type error
+pub fn check_types(nodes: &BTreeMap<String, Kind>, edges: &[Edge]) -> Result<BTreeMap<String, NodeTypes>, Vec<Diagnostic>> {
+    let (types, errors) = infer_types(nodes, edges);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(types)
+}
@@ -0,0 +1,152 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use diagnostic::*;
+use parser::Edge;
+use typeck::Kind;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Visiting,
+    Done,
+}
+
+struct Cycles<'a> {
+    outgoing: HashMap<&'a str, Vec<&'a Edge>>,
+    marks: HashMap<&'a str, Mark>,
+    path: Vec<&'a str>,
+    errors: Vec<Diagnostic>,
+}
+
+impl<'a> Cycles<'a> {
+    fn visit(&mut self, key: &'a str) {
+        self.marks.insert(key, Mark::Visiting);
+        self.path.push(key);
+
+        let edges = self.outgoing.get(key).cloned().unwrap_or_else(Vec::new);
+        for edge in edges {
+            let to = edge.to.as_str();
+            match self.marks.get(to).cloned() {
+                Some(Mark::Done) => {},
+                Some(Mark::Visiting) => {
+                    let start = self.path.iter().position(|k| *k == to).unwrap_or(0);
+                    let mut cycle = self.path[start..].to_vec();
+                    cycle.push(to);
+
+                    self.errors.push(
+                        Diagnostic::error(format!("graph contains a cycle through nodes {}", cycle.join(" -> ")))
+                            .at_node(to)
+                            .at_edge(edge.index)
+                    );
+                },
+                None => self.visit(to),
+            }
+        }
+
+        self.path.pop();
+        self.marks.insert(key, Mark::Done);
+    }
+}
+
+/// Check the shape of the graph before it is handed to rasen: every edge
+/// must target an existing input of its destination, each input takes at
+/// most one edge, required inputs are connected and there are no cycles.
+/// Inputs in `fed` take an edge from a node that was already reported and
+/// count as connected
+pub fn validate(nodes: &BTreeMap<String, Kind>, edges: &[Edge], fed: &HashSet<(String, u32)>) -> Vec<Diagnostic> {
+    let mut errors = Vec::new();
+    let mut connected: HashMap<&str, BTreeMap<u32, &Edge>> = HashMap::new();
+
+    for edge in edges {
+        let kind = match nodes.get(&edge.to) {
+            Some(kind) => kind,
+            None => continue,
+        };
+
+        let arity = kind.arity();
+        match *kind {
+            Kind::Unknown => {},
+            _ if edge.input >= arity => {
+                errors.push(
+                    Diagnostic::error(format!("node has {} inputs, edge targets input {}", arity, edge.input))
+                        .at_node(edge.to.as_str())
+                        .at_edge(edge.index)
+                        .at_pin(edge.input)
+                );
+                continue;
+            },
+            _ => {},
+        }
+
+        let inputs = connected.entry(edge.to.as_str()).or_insert_with(BTreeMap::new);
+        if let Some(first) = inputs.get(&edge.input) {
+            errors.push(
+                Diagnostic::error(format!("input {} is already connected by edge {}", edge.input, first.index))
+                    .at_node(edge.to.as_str())
+                    .at_edge(edge.index)
+                    .at_pin(edge.input)
+            );
+            continue;
+        }
+
+        inputs.insert(edge.input, edge);
+    }
+
+    for (key, kind) in nodes {
+        // Construct nodes take a variable number of inputs, which must
+        // still be connected without gaps
+        let required = match *kind {
+            Kind::Construct(_) => {
+                let last = connected.get(key.as_str())
+                    .and_then(|inputs| inputs.keys().last().cloned())
+                    .into_iter()
+                    .chain(fed.iter().filter(|&&(ref to, _)| to == key).map(|&(_, pin)| pin))
+                    .max();
+
+                match last {
+                    Some(last) => last + 1,
+                    None => 1,
+                }
+            },
+            Kind::Unknown => 0,
+            _ => kind.arity(),
+        };
+
+        for pin in 0..required {
+            let is_connected = connected.get(key.as_str())
+                .map(|inputs| inputs.contains_key(&pin))
+                .unwrap_or(false);
+            let is_connected = is_connected || fed.contains(&(key.clone(), pin));
+
+            if !is_connected {
+                errors.push(
+                    Diagnostic::error(format!("input {} is not connected", pin))
+                        .at_node(key.as_str())
+                        .at_pin(pin)
+                );
+            }
+        }
+    }
+
+    let mut outgoing = HashMap::new();
+    for edge in edges {
+        outgoing.entry(edge.from.as_str())
+            .or_insert_with(Vec::new)
+            .push(edge);
+    }
+
+    let mut cycles = Cycles {
+        outgoing: outgoing,
+        marks: HashMap::new(),
+        path: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for key in nodes.keys() {
+        if !cycles.marks.contains_key(key.as_str()) {
+            cycles.visit(key);
+        }
+    }
+
+    errors.extend(cycles.errors);
+    errors
+}
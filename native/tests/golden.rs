@@ -0,0 +1,92 @@
+extern crate rasen_bridge;
+extern crate serde_json;
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+
+use serde_json::Value;
+
+fn operand_text(operand: &Value) -> String {
+    let kind = operand.find("operand").and_then(Value::as_str).unwrap();
+    let value = operand.find("value").unwrap();
+
+    match (kind, value) {
+        ("Id", &Value::String(ref name)) | ("Type", &Value::String(ref name)) => format!("%{}", name),
+        ("Id", id) | ("Type", id) => format!("%{}", id.as_u64().unwrap()),
+        ("String", &Value::String(ref text)) => format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"")),
+        (_, &Value::String(ref text)) => text.clone(),
+        (_, &Value::I64(val)) => format!("{}", val),
+        (_, &Value::U64(val)) => format!("{}", val),
+        (_, &Value::F64(val)) => format!("{}", val),
+        (_, val) => panic!("unexpected operand value {:?}", val),
+    }
+}
+
+// Print an instruction of the JSON listing the way spirv-dis would
+fn instruction_text(inst: &Value) -> String {
+    let class = inst.find("class").and_then(Value::as_str).unwrap();
+    let operands: Vec<_> = inst.find("operands").and_then(Value::as_array).unwrap()
+        .iter()
+        .map(operand_text)
+        .collect();
+
+    if class == ";" {
+        return format!("; {}", operands.join(" "));
+    }
+
+    let mut line = match inst.find("result_id") {
+        Some(&Value::String(ref name)) => format!("{:>12} = ", format!("%{}", name)),
+        Some(id) => format!("{:>12} = ", format!("%{}", id.as_u64().unwrap())),
+        None => format!("{:15}", ""),
+    };
+
+    line.push_str(class);
+    for operand in operands {
+        line.push(' ');
+        line.push_str(&operand);
+    }
+
+    line
+}
+
+// Compile a graph and compare its text listing with the expected one, set
+// UPDATE_FIXTURES to rewrite the expected listing after a compiler change
+fn check_fixture(graph: &str, expected: &str, fixture: &str) {
+    let program = rasen_bridge::compile(graph, 0).unwrap();
+    let listing = program.listing(0).unwrap();
+
+    let text = listing.to_text();
+    let value = listing.to_value();
+    let lines: Vec<_> = value.find("instructions").and_then(Value::as_array).unwrap()
+        .iter()
+        .map(instruction_text)
+        .collect();
+    assert_eq!(lines, text.lines().collect::<Vec<_>>());
+
+    if env::var_os("UPDATE_FIXTURES").is_some() {
+        let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+        File::create(path).unwrap().write_all(text.as_bytes()).unwrap();
+        return;
+    }
+
+    assert_eq!(text, expected);
+}
+
+#[test]
+fn normalize_matches_fixture() {
+    check_fixture(
+        include_str!("fixtures/normalize.json"),
+        include_str!("fixtures/normalize.spvasm"),
+        "normalize.spvasm"
+    );
+}
+
+#[test]
+fn constants_match_fixture() {
+    check_fixture(
+        include_str!("fixtures/constants.json"),
+        include_str!("fixtures/constants.spvasm"),
+        "constants.spvasm"
+    );
+}